@@ -0,0 +1,45 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use suono::bits::BitReader;
+use suono::decode::DecodingReadProxy;
+use suono::frame::FrameBuffer;
+use suono::stream::Stream;
+
+static FIXTURES: &[(&str, &[u8])] = &[
+    ("stereo16", include_bytes!("../testdata/stereo16.flac")),
+    ("stereo24", include_bytes!("../testdata/stereo24.flac")),
+    ("stereo32", include_bytes!("../testdata/stereo32.flac"))
+];
+
+// decoding every frame into the same `FrameBuffer`s, which only rewind their cursor between
+// frames, against allocating fresh ones for each frame
+fn bench_frame_buffers(c: &mut Criterion) {
+    for &(name, file) in FIXTURES {
+        let mut bytes: &[u8] = file;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let stream = Stream::new(&mut BitReader::new(&mut proxy)).unwrap();
+        let frames = &file[stream.first_frame_offset() as usize..];
+        let mut group = c.benchmark_group(name);
+        group.bench_function("reused", |b| b.iter(|| {
+            let mut bytes: &[u8] = black_box(frames);
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let mut blocks: Vec<FrameBuffer> = stream.allocate_blocks();
+            while stream.decode_next_frame(&mut reader, &mut blocks).unwrap().is_some() {}
+        }));
+        group.bench_function("fresh", |b| b.iter(|| {
+            let mut bytes: &[u8] = black_box(frames);
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            loop {
+                let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(); stream.stream_info.number_of_channels];
+                if stream.decode_next_frame(&mut reader, &mut blocks).unwrap().is_none() {
+                    break;
+                }
+            }
+        }));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_frame_buffers);
+criterion_main!(benches);
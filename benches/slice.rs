@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use suono::bits::BitReader;
+use suono::decode::{Decode, DecodingReadProxy};
+use suono::frame::Frame;
+use suono::slice::SliceBitReader;
+use suono::stream::Stream;
+
+static FIXTURES: &[(&str, &[u8])] = &[
+    ("stereo16", include_bytes!("../testdata/stereo16.flac")),
+    ("stereo24", include_bytes!("../testdata/stereo24.flac")),
+    ("stereo32", include_bytes!("../testdata/stereo32.flac"))
+];
+
+fn decode(reader: &mut dyn Decode) -> usize {
+    let stream = Stream::new(reader).unwrap();
+    let mut num_samples: usize = 0;
+    stream.decode_frames(reader, |frame: &Frame| {
+        num_samples += frame.header.block_size;
+    }).unwrap();
+    num_samples
+}
+
+// `SliceBitReader` against `BitReader` over `DecodingReadProxy`, decoding a whole stream in memory
+fn bench_readers(c: &mut Criterion) {
+    for &(name, file) in FIXTURES {
+        let mut group = c.benchmark_group(name);
+        group.bench_function("proxy", |b| b.iter(|| {
+            let mut bytes: &[u8] = black_box(file);
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            decode(&mut BitReader::new(&mut proxy))
+        }));
+        group.bench_function("slice", |b| b.iter(|| {
+            decode(&mut SliceBitReader::new(black_box(file)))
+        }));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_readers);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use suono::bits::BitReader;
+use suono::decode::DecodingReadProxy;
+use suono::frame::Frame;
+use suono::stream::Stream;
+
+static STEREO16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+
+// `Stream::decode_stereo` against decoding 32-bit frames and splitting them by hand,
+// both from the first frame of a stream parsed up front
+fn bench_decode_stereo(c: &mut Criterion) {
+    let mut bytes: &[u8] = STEREO16;
+    let mut proxy = DecodingReadProxy::new(&mut bytes);
+    let stream = Stream::new(&mut BitReader::new(&mut proxy)).unwrap();
+    let frames = &STEREO16[stream.first_frame_offset() as usize..];
+    let mut group = c.benchmark_group("stereo16");
+    group.bench_function("decode_stereo", |b| b.iter(|| {
+        let mut bytes: &[u8] = black_box(frames);
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        stream.decode_stereo(&mut reader).unwrap()
+    }));
+    group.bench_function("decode_frames", |b| b.iter(|| {
+        let mut bytes: &[u8] = black_box(frames);
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut left: Vec<i16> = Vec::with_capacity(stream.stream_info.reserved_samples());
+        let mut right: Vec<i16> = Vec::with_capacity(stream.stream_info.reserved_samples());
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            left.extend(frame.blocks[0].iter().map(|&x| x as i16));
+            right.extend(frame.blocks[1].iter().map(|&x| x as i16));
+        }).unwrap();
+        (left, right)
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode_stereo);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+#![no_main]
+
+// decodes arbitrary bytes, which may fail with an error but never panic.
+// run with `cargo fuzz run decode`, seeding the corpus with the files of testdata
+use libfuzzer_sys::fuzz_target;
+use suono::bits::BitReader;
+use suono::decode::DecodingReadProxy;
+use suono::frame::Frame;
+use suono::options::DecodeOptions;
+use suono::stream::Stream;
+
+fn decode(data: &[u8], options: DecodeOptions) {
+    let mut bytes: &[u8] = data;
+    let mut proxy = DecodingReadProxy::new(&mut bytes);
+    let mut reader = BitReader::new(&mut proxy);
+    let stream = match Stream::with_options(&mut reader, options) {
+        Ok(stream) => stream,
+        Err(_) => return
+    };
+    for metadata in stream.metadata() {
+        let _ = metadata;
+    }
+    let _ = stream.decode_frames(&mut reader, |frame: &Frame| {
+        let _ = frame.interleaved();
+        let _ = frame.to_f32_interleaved();
+    });
+}
+
+fn decode_lossy(data: &[u8]) {
+    let mut bytes: &[u8] = data;
+    let mut proxy = DecodingReadProxy::new(&mut bytes);
+    let mut reader = BitReader::new(&mut proxy);
+    if let Ok(stream) = Stream::new(&mut reader) {
+        let _ = stream.decode_frames_lossy(&mut reader, |_: &Frame<i64>| (), |_| ());
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    decode(data, DecodeOptions { verify_md5: true, ..Default::default() });
+    // the CRCs reject most of the mutations otherwise, before the subframes are reached
+    decode(data, DecodeOptions { verify_crc: false, ..Default::default() });
+    decode_lossy(data);
+});
@@ -1,6 +1,6 @@
 
-use std::io::Read;
-use std::io::Result;
+use super::io::{self, Read};
+use super::io::Result;
 
 use super::bitvec::Bitvec;
 
@@ -16,26 +16,90 @@ pub trait BitRead {
     fn read_u32_bits(&mut self, n: usize) -> Result<u32>;
     fn read_u64_bits(&mut self, n: usize) -> Result<u64>;
     fn read_bitvec(&mut self, v: &mut Bitvec, n: usize) -> Result<()>;
+    fn skip_bits(&mut self, n: usize) -> Result<()>;
+    // drops the rest of the current byte
     fn align_to_byte(&mut self);
+    fn is_byte_aligned(&self) -> bool;
+    // the number of bits already read from the current byte
+    fn bits_into_byte(&self) -> usize;
 
-    fn read_unary(&mut self) -> Result<u32>;
+    // counts the 0 bits until a 1 bit, which is consumed as well
+    fn read_unary(&mut self) -> Result<u32> {
+        self.read_unary_bounded(u32::MAX)?.ok_or_else(unary_overflow)
+    }
+
+    // same as `read_unary`, but returns None as soon as more than `limit` 0 bits are read,
+    // leaving the reader in the middle of the run. implementors may override it with a
+    // faster one, as `BitReader` does.
+    fn read_unary_bounded(&mut self, limit: u32) -> Result<Option<u32>> {
+        let mut n: u32 = 0;
+        while !self.read_bool()? {
+            if n == limit {
+                return Ok(None)
+            }
+            n += 1;
+        }
+        Ok(Some(n))
+    }
+
+    // reads `n` bits as a two's complement integer, where 0 bits read as 0
+    fn read_i32_bits(&mut self, n: usize) -> Result<i32> {
+        assert!(n <= 32);
+        Ok(self.read_i64_bits(n)? as i32)
+    }
+
+    fn read_i64_bits(&mut self, n: usize) -> Result<i64> {
+        if n == 0 {
+            return Ok(0)
+        }
+        let m = 64 - n;
+        let value = self.read_u64_bits(n)?;
+        Ok(((value << m) as i64) >> m)
+    }
+
+    // big-endian, as the lengths in metadata headers and STREAMINFO
+    fn read_u24(&mut self) -> Result<u32> {
+        self.read_u32_bits(24)
+    }
+
+    // little-endian, as the fields of VORBIS_COMMENT
+    fn read_u16_le(&mut self) -> Result<u16> {
+        Ok(self.read_u16()?.swap_bytes())
+    }
+
+    fn read_u32_le(&mut self) -> Result<u32> {
+        Ok(self.read_u32()?.swap_bytes())
+    }
 }
 
-pub struct BitReader<'a, Source> {
-    pub(super) source: &'a mut Source,
+// reads bits from the source, which is either borrowed as by `new` or owned
+pub struct BitReader<Source> {
+    pub(super) source: Source,
     queue: u64,
     queue_count: isize
 }
 
-impl<'a, Source: Read> BitReader<'a, Source> {
+impl<'a, Source: Read> BitReader<&'a mut Source> {
     pub fn new(source: &'a mut Source) -> Self {
+        BitReader::from_owned(source)
+    }
+}
+
+impl<Source: Read> BitReader<Source> {
+    // owns the source, so that the reader can be returned from a function or kept in a struct
+    pub fn from_owned(source: Source) -> Self {
         BitReader {
-            source: source,
+            source,
             queue: 0,
             queue_count: 0
         }
     }
 
+    // gives the source back, dropping the bits queued but not read yet
+    pub fn into_inner(self) -> Source {
+        self.source
+    }
+
     #[inline]
     fn read_value(&mut self, n: usize) -> Result<u64> {
         assert!(n <= 64);
@@ -64,9 +128,83 @@ impl<'a, Source: Read> BitReader<'a, Source> {
         }
         Ok(result)
     }
+
+    // returns the next `n` bits without consuming them. the bytes pulled from the source
+    // to do so are kept in the queue, which the source counts as read.
+    pub fn peek_bits(&mut self, n: usize) -> Result<u64> {
+        // the queue holds fewer than 8 bits besides the peeked ones
+        assert!(n <= 56);
+        let n_bits = (n as isize) - self.queue_count;
+        if n_bits > 0 {
+            let n_bytes = ((n_bits - 1) >> 3) + 1;
+            let mut array: [u8; 8] = [0u8; 8];
+            let offset = (8 - n_bytes) as usize;
+            self.source.read_exact(&mut array[offset..])?;
+            let loaded = u64::from_be_bytes(array);
+            self.queue = (self.queue << (n_bytes * 8)) | loaded;
+            self.queue_count += n_bytes * 8;
+        }
+        Ok(self.queue >> (self.queue_count - (n as isize)))
+    }
+
+    // pulls as many whole bytes into the queue as fit in 56 bits, but no more than
+    // the next `n` bits which are certain to be read, e.g. by the rest of a subframe
+    pub(super) fn prefetch(&mut self, n: usize) -> Result<()> {
+        let room = (56 - self.queue_count) >> 3;
+        let certain = ((n as isize) - self.queue_count) >> 3;
+        let n_bytes = room.min(certain);
+        if n_bytes <= 0 {
+            return Ok(())
+        }
+        let mut array: [u8; 8] = [0u8; 8];
+        let offset = (8 - n_bytes) as usize;
+        self.source.read_exact(&mut array[offset..])?;
+        let loaded = u64::from_be_bytes(array);
+        self.queue = (self.queue << (n_bytes * 8)) | loaded;
+        self.queue_count += n_bytes * 8;
+        Ok(())
+    }
 }
 
-impl<'a, Source: Read> BitRead for BitReader<'a, Source> {
+impl<Source> BitReader<Source> {
+    // reads a Rice code as its quotient and remainder if the queue holds the whole code
+    #[inline]
+    pub(super) fn read_queued_rice(&mut self, parameter: usize) -> Option<(u32, u32)> {
+        if self.queue_count == 0 {
+            return None
+        }
+        let v = self.queue << (64 - self.queue_count);
+        let unary = v.leading_zeros() as isize;
+        let len = unary + 1 + (parameter as isize);
+        if len > self.queue_count {
+            return None
+        }
+        let remaining = self.queue_count - len;
+        let lsb = (self.queue >> remaining) & ((1u64 << parameter) - 1);
+        self.queue &= (1u64 << remaining) - 1;
+        self.queue_count = remaining;
+        Some((unary as u32, lsb as u32))
+    }
+
+    // returns the next byte in the queue if it holds a whole one, that is only after peeking
+    pub(super) fn queued_u8(&self) -> Option<u8> {
+        if self.queue_count >= 8 {
+            Some((self.queue >> (self.queue_count - 8)) as u8)
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn queued_u16(&self) -> Option<u16> {
+        if self.queue_count >= 16 {
+            Some((self.queue >> (self.queue_count - 16)) as u16)
+        } else {
+            None
+        }
+    }
+}
+
+impl<Source: Read> BitRead for BitReader<Source> {
     fn read_bool(&mut self) -> Result<bool> {
         let value = self.read_value(1)?;
         Ok((value & 1u64) == 1u64)
@@ -121,8 +259,13 @@ impl<'a, Source: Read> BitRead for BitReader<'a, Source> {
         Ok(value)
     }
 
-    fn read_bitvec(&mut self, vec: &mut Bitvec, n: usize) -> Result<()> {
-        assert!(self.queue_count < 8);
+    fn read_bitvec(&mut self, vec: &mut Bitvec, mut n: usize) -> Result<()> {
+        // drain the whole bytes left by peeking
+        while self.queue_count >= 8 && n >= 8 {
+            let byte = self.read_value(8)? as u8;
+            vec.write_bits(byte, 8);
+            n -= 8;
+        }
         let queue = self.queue;
         let n_bits = (n as isize) - self.queue_count;
         if n_bits > 0 {
@@ -130,7 +273,7 @@ impl<'a, Source: Read> BitRead for BitReader<'a, Source> {
             // flush the existing bits
             vec.write_bits((queue & 0xffu64) as u8, self.queue_count as usize);
             // extend contiguous bytes
-            vec.write_bytes(self.source, (n_bytes - 1) as usize)?;
+            vec.write_bytes(&mut self.source, (n_bytes - 1) as usize)?;
             // truncate the last byte if necessary
             let mut buf: [u8; 1] = [0u8; 1];
             self.source.read_exact(&mut buf)?;
@@ -156,12 +299,38 @@ impl<'a, Source: Read> BitRead for BitReader<'a, Source> {
         Ok(())
     }
 
+    fn skip_bits(&mut self, mut n: usize) -> Result<()> {
+        // the queue first
+        let queued = (self.queue_count as usize).min(n);
+        self.read_value(queued)?;
+        n -= queued;
+        // then the whole bytes through a buffer on the stack, not to allocate for large blocks
+        let mut buf: [u8; 256] = [0u8; 256];
+        let mut n_bytes = n >> 3;
+        while n_bytes > 0 {
+            let len = n_bytes.min(buf.len());
+            self.source.read_exact(&mut buf[..len])?;
+            n_bytes -= len;
+        }
+        self.read_value(n & 7)?;
+        Ok(())
+    }
+
     fn align_to_byte(&mut self) {
-        self.queue = 0;
-        self.queue_count = 0;
+        // keeps the whole bytes left by peeking
+        self.queue_count &= !7;
+        self.queue &= (1u64 << self.queue_count) - 1;
     }
 
-    fn read_unary(&mut self) -> Result<u32> {
+    fn is_byte_aligned(&self) -> bool {
+        self.bits_into_byte() == 0
+    }
+
+    fn bits_into_byte(&self) -> usize {
+        ((8 - (self.queue_count & 7)) & 7) as usize
+    }
+
+    fn read_unary_bounded(&mut self, limit: u32) -> Result<Option<u32>> {
         // consume queue
         let mut n: u32 = 0;
         if self.queue_count > 0 {
@@ -169,13 +338,16 @@ impl<'a, Source: Read> BitRead for BitReader<'a, Source> {
                 n = self.queue_count as u32;
                 self.queue = 0;
                 self.queue_count = 0;
+                if n > limit {
+                    return Ok(None)
+                }
             } else {
                 let v = self.queue << (64 - self.queue_count);
                 let unary = v.leading_zeros();
                 let new_count = self.queue_count - (unary + 1) as isize;
                 self.queue &= (1u64 << new_count) - 1;
                 self.queue_count = new_count;
-                return Ok(unary);
+                return Ok(Some(unary).filter(|&x| x <= limit));
             }
         }
         assert_eq!(self.queue_count, 0);
@@ -187,20 +359,31 @@ impl<'a, Source: Read> BitRead for BitReader<'a, Source> {
             if v != 0 {
                 break;
             }
-            n += 8;
+            // stops reading the run once past the limit
+            n = match n.checked_add(8) {
+                Some(n) if n <= limit => n,
+                _ => return Ok(None)
+            };
         }
         let u = v.leading_zeros();
         let new_count = (8 - (u + 1)) as isize;
         let mask = (1u64 << new_count) - 1;
         self.queue = (v as u64) & mask;
         self.queue_count = new_count;
-        Ok(n + u)
+        Ok(n.checked_add(u).filter(|&x| x <= limit))
     }
 }
 
+// a run of 0 bits too long to count, such as in a stream of zeros
+pub(super) fn unary_overflow() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "unary code too long")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
     use super::super::bitvec::BitvecBlock;
 
     #[test]
@@ -253,6 +436,111 @@ mod tests {
         assert_eq!(reader.read_u64_bits(1).unwrap(), 0b0);
     }
 
+    #[test]
+    fn test_signed() {
+        let mut bytes: &[u8] = &[0b1100_0110, 0b1011_0011, 0b0011_0011, 0xff, 0xff, 0xff, 0xff];
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_i32_bits(3).unwrap(), -2);
+        assert_eq!(reader.read_i32_bits(3).unwrap(), 1);
+        assert_eq!(reader.read_i32_bits(0).unwrap(), 0);
+        assert_eq!(reader.read_i64_bits(2).unwrap(), -2);
+        assert_eq!(reader.read_i32_bits(8).unwrap(), -77);
+        assert_eq!(reader.read_i64_bits(8).unwrap(), 51);
+        assert_eq!(reader.read_i32_bits(32).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_signed_widths() {
+        // zero bits read nothing, and 64 bits take the sign bit as is
+        let mut bytes: &[u8] = &[0x80, 0, 0, 0, 0, 0, 0, 0x01, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_i64_bits(0).unwrap(), 0);
+        assert_eq!(reader.read_i64_bits(64).unwrap(), i64::MIN + 1);
+        assert_eq!(reader.read_i64_bits(0).unwrap(), 0);
+        assert_eq!(reader.read_i64_bits(64).unwrap(), i64::MAX);
+        assert!(reader.read_i64_bits(0).is_ok());
+        assert!(reader.read_i64_bits(1).is_err());
+    }
+
+    #[test]
+    fn test_endianness() {
+        let mut bytes: &[u8] = &[0x12, 0x34, 0x56, 0x12, 0x34, 0x12, 0x34, 0x56, 0x78, 0xff];
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_u24().unwrap(), 0x12_3456);
+        assert_eq!(reader.read_u16_le().unwrap(), 0x3412);
+        assert_eq!(reader.read_u32_le().unwrap(), 0x7856_3412);
+        assert_eq!(reader.read_u8().unwrap(), 0xff);
+    }
+
+    #[test]
+    fn test_peek_bits() {
+        let mut bytes: &[u8] = &[0b1011_0110, 0b1100_1100, 0b1111_0110, 0b1100_1001, 0b1000_1001];
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_u8_bits(3).unwrap(), 0b101);
+        assert_eq!(reader.peek_bits(2).unwrap(), 0b10);
+        assert_eq!(reader.peek_bits(13).unwrap(), 0b10110_11001100);
+        assert_eq!(reader.peek_bits(0).unwrap(), 0);
+        assert_eq!(reader.read_u8_bits(5).unwrap(), 0b10110);
+        assert_eq!(reader.peek_bits(20).unwrap(), 0b1100_1100_1111_0110_1100);
+        assert_eq!(reader.read_u16().unwrap(), 0b11001100_11110110);
+        assert_eq!(reader.read_u16().unwrap(), 0b11001001_10001001);
+        assert!(reader.peek_bits(1).is_err());
+    }
+
+    #[test]
+    fn test_peek_bits_then_others() {
+        let mut bytes: &[u8] = &[0b1011_0100, 0b0000_0000, 0b0001_0110, 0b1100_1001];
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.peek_bits(32).unwrap(), 0b10110100_00000000_00010110_11001001);
+        let mut vec = Bitvec::new();
+        reader.read_bitvec(&mut vec, 6).unwrap();
+        assert_eq!(vec.blocks, vec![BitvecBlock::Bits(0b101101, 6)]);
+        assert_eq!(reader.read_unary().unwrap(), 13);
+        // keeps the last byte after dropping the rest of the current one
+        reader.align_to_byte();
+        assert_eq!(reader.queued_u8(), Some(0b1100_1001));
+        assert_eq!(reader.read_u8().unwrap(), 0b1100_1001);
+        assert_eq!(reader.queued_u8(), None);
+    }
+
+    #[test]
+    fn test_skip_bits() {
+        let mut bytes: Vec<u8> = vec![0b1011_0110];
+        bytes.extend((0..1000).map(|x| x as u8));
+        let mut slice: &[u8] = &bytes;
+        let mut reader = BitReader::new(&mut slice);
+        reader.skip_bits(0).unwrap();
+        reader.skip_bits(3).unwrap();
+        assert_eq!(reader.read_u8_bits(2).unwrap(), 0b10);
+        // the rest of the queue and 600 bytes, then 4 bits into 0x58
+        reader.skip_bits(3 + 600 * 8 + 4).unwrap();
+        assert_eq!(reader.read_u8_bits(4).unwrap(), 0x8);
+        // the queue alone after peeking 0x59, 0x5a and 0x5b
+        reader.peek_bits(24).unwrap();
+        reader.skip_bits(12).unwrap();
+        assert_eq!(reader.read_u16().unwrap(), 0xa5b5);
+        assert!(reader.skip_bits(8 * 1000).is_err());
+    }
+
+    #[test]
+    fn test_byte_alignment() {
+        let mut bytes: &[u8] = &[0b1011_0110, 0b1100_1100, 0b1111_0110];
+        let mut reader = BitReader::new(&mut bytes);
+        assert!(reader.is_byte_aligned());
+        assert_eq!(reader.read_u8_bits(3).unwrap(), 0b101);
+        assert!(!reader.is_byte_aligned());
+        assert_eq!(reader.bits_into_byte(), 3);
+        // peeking does not move the position
+        reader.peek_bits(16).unwrap();
+        assert_eq!(reader.bits_into_byte(), 3);
+        reader.align_to_byte();
+        assert!(reader.is_byte_aligned());
+        assert_eq!(reader.read_u8_bits(7).unwrap(), 0b110_0110);
+        assert_eq!(reader.bits_into_byte(), 7);
+        assert_eq!(reader.read_u16_bits(9).unwrap(), 0b0_1111_0110);
+        assert_eq!(reader.bits_into_byte(), 0);
+    }
+
     #[test]
     fn test_unary() {
         let mut bytes: &[u8] = &[0b10110111];
@@ -263,7 +551,71 @@ mod tests {
         assert_eq!(reader.read_unary().unwrap(), 1);
         assert_eq!(reader.read_unary().unwrap(), 0);
         assert_eq!(reader.read_unary().unwrap(), 0);
-        // TODO: add more cases
+        // across bytes
+        let mut bytes: &[u8] = &[0b0000_0000, 0b0000_0000, 0b0001_0100, 0b0000_0001];
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_unary().unwrap(), 19);
+        assert_eq!(reader.read_unary().unwrap(), 1);
+        assert_eq!(reader.read_unary().unwrap(), 9);
+        assert!(reader.read_unary().is_err());
+    }
+
+    #[test]
+    fn test_unary_bounded() {
+        let data: &[u8] = &[0b0000_0000, 0b0000_0000, 0b0001_0100, 0b0000_0001];
+        let mut bytes: &[u8] = data;
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_unary_bounded(19).unwrap(), Some(19));
+        assert_eq!(reader.read_unary_bounded(0).unwrap(), None);
+        // stops within the run, before the last byte
+        let mut bytes: &[u8] = data;
+        let mut reader = BitReader::new(&mut bytes);
+        assert_eq!(reader.read_unary_bounded(15).unwrap(), None);
+        assert_eq!(bytes.len(), 2);
+        let mut bytes: &[u8] = data;
+        let mut reader = DefaultBitRead(BitReader::new(&mut bytes));
+        assert_eq!(reader.read_unary_bounded(18).unwrap(), None);
+        assert_eq!(reader.read_unary_bounded(0).unwrap(), Some(0));
+    }
+
+    // relies on the default methods of the trait, forwarding the rest to a BitReader
+    struct DefaultBitRead<'a, 'b>(BitReader<&'a mut &'b [u8]>);
+
+    impl BitRead for DefaultBitRead<'_, '_> {
+        fn read_bool(&mut self) -> Result<bool> { self.0.read_bool() }
+        fn read_u8(&mut self) -> Result<u8> { self.0.read_u8() }
+        fn read_u16(&mut self) -> Result<u16> { self.0.read_u16() }
+        fn read_u32(&mut self) -> Result<u32> { self.0.read_u32() }
+        fn read_u64(&mut self) -> Result<u64> { self.0.read_u64() }
+        fn read_u128(&mut self) -> Result<u128> { self.0.read_u128() }
+        fn read_u8_bits(&mut self, n: usize) -> Result<u8> { self.0.read_u8_bits(n) }
+        fn read_u16_bits(&mut self, n: usize) -> Result<u16> { self.0.read_u16_bits(n) }
+        fn read_u32_bits(&mut self, n: usize) -> Result<u32> { self.0.read_u32_bits(n) }
+        fn read_u64_bits(&mut self, n: usize) -> Result<u64> { self.0.read_u64_bits(n) }
+        fn read_bitvec(&mut self, v: &mut Bitvec, n: usize) -> Result<()> { self.0.read_bitvec(v, n) }
+        fn skip_bits(&mut self, n: usize) -> Result<()> { self.0.skip_bits(n) }
+        fn align_to_byte(&mut self) { self.0.align_to_byte() }
+        fn is_byte_aligned(&self) -> bool { self.0.is_byte_aligned() }
+        fn bits_into_byte(&self) -> usize { self.0.bits_into_byte() }
+    }
+
+    #[test]
+    fn test_default_unary() {
+        let data: &[u8] = &[0b1011_0111, 0b0000_0000, 0b0000_0000, 0b0001_0100, 0b0000_0001];
+        let mut bytes: &[u8] = data;
+        let mut expected: Vec<u32> = Vec::new();
+        let mut reader = BitReader::new(&mut bytes);
+        while let Ok(n) = reader.read_unary() {
+            expected.push(n);
+        }
+        let mut bytes: &[u8] = data;
+        let mut reader = DefaultBitRead(BitReader::new(&mut bytes));
+        let mut actual: Vec<u32> = Vec::new();
+        while let Ok(n) = reader.read_unary() {
+            actual.push(n);
+        }
+        assert_eq!(actual, expected);
+        assert_eq!(actual, vec![0, 1, 0, 1, 0, 0, 19, 1, 9]);
     }
 
     #[test]
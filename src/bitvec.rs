@@ -1,6 +1,8 @@
 
-use std::io::Result;
-use std::io::Read;
+use alloc::vec;
+use alloc::vec::Vec;
+use super::io::Result;
+use super::io::Read;
 
 #[derive(PartialEq, Debug)]
 pub enum BitvecBlock {
@@ -13,6 +15,12 @@ pub struct Bitvec {
     pub blocks: Vec<BitvecBlock>
 }
 
+impl Default for Bitvec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Bitvec {
     pub fn new() -> Self {
         Bitvec {
@@ -67,7 +75,7 @@ impl Bitvec {
         }
     }
 
-    pub fn write_bytes(&mut self, reader: &mut Read, n: usize) -> Result<()> {
+    pub fn write_bytes(&mut self, reader: &mut dyn Read, n: usize) -> Result<()> {
         if n == 0 {
             return Ok(())
         }
@@ -81,8 +89,7 @@ impl Bitvec {
                 Ok(())
             },
             _ => {
-                let mut vec: Vec<u8> = Vec::new();
-                vec.resize(n, 0);
+                let mut vec: Vec<u8> = vec![0; n];
                 let slice = &mut vec[..];
                 reader.read_exact(slice)?;
                 self.blocks.push(BitvecBlock::Bytes(vec));
@@ -90,6 +97,58 @@ impl Bitvec {
             }
         }
     }
+
+    // the bits written so far, most significant first
+    pub fn iter_bits(&self) -> impl Iterator<Item = bool> + '_ {
+        self.blocks.iter().flat_map(|block| {
+            let (bytes, bits): (&[u8], Option<(u8, usize)>) = match block {
+                BitvecBlock::Bytes(vec) => (vec, None),
+                BitvecBlock::Bits(w, q) => (&[], Some((*w, *q)))
+            };
+            let bytes = bytes.iter().flat_map(|&u| (0..8).rev().map(move |i| (u >> i) & 1 != 0));
+            let bits = bits.into_iter().flat_map(|(w, q)| (0..q).rev().map(move |i| (w >> i) & 1 != 0));
+            bytes.chain(bits)
+        })
+    }
+
+    // packs the bits written so far into whole bytes, followed by the rest of the bits
+    // held in the lower bits of a byte along with their number, which is less than 8
+    pub fn into_bytes(self) -> (Vec<u8>, u8, usize) {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut w: u8 = 0;
+        let mut q: usize = 0;
+        for block in self.blocks {
+            match block {
+                // no need to shift while aligned
+                BitvecBlock::Bytes(vec) if q == 0 => {
+                    if bytes.is_empty() {
+                        bytes = vec;
+                    } else {
+                        bytes.extend_from_slice(&vec);
+                    }
+                },
+                BitvecBlock::Bytes(vec) => {
+                    for u in vec {
+                        bytes.push((w << (8 - q)) | (u >> q));
+                        w = u & (((1u16 << q) - 1) as u8);
+                    }
+                },
+                BitvecBlock::Bits(u, n) => {
+                    let u = u & (((1u16 << n) - 1) as u8);
+                    if q + n < 8 {
+                        w = (w << n) | u;
+                        q += n;
+                    } else {
+                        let overflow = q + n - 8;
+                        bytes.push((((w as u16) << (8 - q)) as u8) | (u >> overflow));
+                        w = u & (((1u16 << overflow) - 1) as u8);
+                        q = overflow;
+                    }
+                }
+            }
+        }
+        (bytes, w, q)
+    }
 }
 
 impl PartialEq for Bitvec {
@@ -145,6 +204,55 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_iter_bits() {
+        let mut bytes: &[u8] = &[0b10110110, 0b11001100];
+        let mut vec = Bitvec::new();
+        vec.write_bits(0b101, 3);
+        vec.write_bytes(&mut bytes, 2).unwrap();
+        vec.write_bits(0b01, 2);
+        let bits: Vec<u8> = vec.iter_bits().map(|x| x as u8).collect();
+        assert_eq!(bits, vec![1, 0, 1, 1, 0, 1, 1, 0, 1, 1, 0, 1, 1, 0, 0, 1, 1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_into_bytes() {
+        let mut vec = Bitvec::new();
+        assert_eq!(vec.into_bytes(), (vec![], 0, 0));
+        // aligned
+        let mut bytes: &[u8] = &[0x66, 0x4c, 0x61, 0x43];
+        vec = Bitvec::new();
+        vec.write_bytes(&mut bytes, 4).unwrap();
+        vec.write_bits(0b110, 3);
+        assert_eq!(vec.into_bytes(), (vec![0x66, 0x4c, 0x61, 0x43], 0b110, 3));
+        // bytes following bits are shifted
+        let mut bytes: &[u8] = &[0b10110110, 0b11001100];
+        vec = Bitvec::new();
+        vec.write_bits(0b101, 3);
+        vec.write_bytes(&mut bytes, 2).unwrap();
+        vec.write_bits(0b01, 2);
+        vec.write_bits(0b1111, 4);
+        assert_eq!(vec.into_bytes(), (vec![0b10110110, 0b11011001, 0b10001111], 0b1, 1));
+    }
+
+    #[test]
+    fn test_into_bytes_matches_iter_bits() {
+        let mut bytes: &[u8] = &[0x12, 0x34, 0x56, 0x78, 0x9a];
+        let mut vec = Bitvec::new();
+        vec.write_bits(0b1, 1);
+        vec.write_bytes(&mut bytes, 2).unwrap();
+        vec.write_bits(0b111111, 6);
+        vec.write_bits(0b0101010, 7);
+        vec.write_bytes(&mut bytes, 3).unwrap();
+        vec.write_bits(0b11, 2);
+        let bits: Vec<bool> = vec.iter_bits().collect();
+        let (packed, rest, n) = vec.into_bytes();
+        assert_eq!(packed.len() * 8 + n, bits.len());
+        let mut unpacked: Vec<bool> = packed.iter().flat_map(|&u| (0..8).rev().map(move |i| (u >> i) & 1 != 0)).collect();
+        unpacked.extend((0..n).rev().map(|i| (rest >> i) & 1 != 0));
+        assert_eq!(unpacked, bits);
+    }
+
     #[test]
     fn test_write_overflow_twice() {
         let mut vec = Bitvec::new();
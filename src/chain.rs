@@ -0,0 +1,87 @@
+use super::io::{self, Read};
+
+// reads through a sequence of buffers as if they were contiguous, e.g. chunks received
+// over a network, so that reads span chunk boundaries without copying them together
+pub struct BufferChain<I: Iterator> {
+    buffers: I,
+    current: Option<I::Item>,
+    position: usize
+}
+
+impl<I> BufferChain<I> where I: Iterator, I::Item: AsRef<[u8]> {
+    pub fn new<T>(buffers: T) -> Self where T: IntoIterator<IntoIter = I, Item = I::Item> {
+        BufferChain {
+            buffers: buffers.into_iter(),
+            current: None,
+            position: 0
+        }
+    }
+}
+
+impl<I> Read for BufferChain<I> where I: Iterator, I::Item: AsRef<[u8]> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+        loop {
+            if let Some(current) = &self.current {
+                let remaining = &current.as_ref()[self.position..];
+                if !remaining.is_empty() {
+                    let n = remaining.len().min(buf.len());
+                    buf[..n].copy_from_slice(&remaining[..n]);
+                    self.position += n;
+                    return Ok(n)
+                }
+            }
+            // skips empty buffers as well
+            match self.buffers.next() {
+                None => return Ok(0),
+                Some(next) => {
+                    self.current = Some(next);
+                    self.position = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use super::super::bits::BitReader;
+    use super::super::decode::DecodingReadProxy;
+    use super::super::stream::Stream;
+
+    static STEREO16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+
+    #[test]
+    fn test_read_across_buffers() {
+        let buffers: Vec<Vec<u8>> = vec![vec![1, 2], vec![], vec![3], vec![4, 5, 6]];
+        let mut chain = BufferChain::new(buffers);
+        let mut buf = [0u8; 4];
+        chain.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+        let mut rest = [0u8; 4];
+        assert_eq!(chain.read(&mut rest).unwrap(), 2);
+        assert_eq!(rest[..2], [5, 6]);
+        assert_eq!(chain.read(&mut rest).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_decode_from_chunks() {
+        // the second frame begins at 183 and ends at 382
+        let chunks: Vec<&[u8]> = vec![&STEREO16[..250], &STEREO16[250..300], &STEREO16[300..]];
+        let mut chain = BufferChain::new(chunks);
+        let mut proxy = DecodingReadProxy::new(&mut chain);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let decoded = stream.decode_stereo(&mut reader).unwrap();
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert_eq!(decoded, stream.decode_stereo(&mut reader).unwrap());
+    }
+}
@@ -0,0 +1,176 @@
+
+use alloc::vec::Vec;
+use super::error::Result;
+use super::decode::Decode;
+use super::frame::{Frame, FrameBuffer};
+use super::stream::Stream;
+
+// decodes frames on demand to fill fixed-size chunks of interleaved samples,
+// retaining the leftover samples of a frame for the next call
+pub struct SampleReader<'a> {
+    stream: &'a Stream,
+    reader: &'a mut dyn Decode,
+    blocks: Vec<FrameBuffer>,
+    pending: Vec<i32>,
+    position: usize,
+    finished: bool
+}
+
+impl<'a> SampleReader<'a> {
+    pub fn new(stream: &'a Stream, reader: &'a mut dyn Decode) -> Self {
+        let info = &stream.stream_info;
+        let blocks: Vec<FrameBuffer> = stream.allocate_blocks();
+        SampleReader {
+            stream,
+            reader,
+            blocks,
+            pending: Vec::with_capacity(info.max_block_size * info.number_of_channels),
+            position: 0,
+            finished: false
+        }
+    }
+
+    // returns the number of samples written, which is less than `out.len()` only at the end of stream
+    pub fn read_samples(&mut self, out: &mut [i32]) -> Result<usize> {
+        let mut written: usize = 0;
+        while written < out.len() {
+            if self.position < self.pending.len() {
+                let n = (out.len() - written).min(self.pending.len() - self.position);
+                out[written..written + n].copy_from_slice(&self.pending[self.position..self.position + n]);
+                written += n;
+                self.position += n;
+                continue;
+            }
+            if self.finished || !self.decode_next()? {
+                break;
+            }
+        }
+        Ok(written)
+    }
+
+    fn decode_next(&mut self) -> Result<bool> {
+        let frame = match Frame::from_reader(self.reader, &self.stream.stream_info, &self.stream.options, &mut self.blocks)? {
+            None => {
+                self.finished = true;
+                return Ok(false)
+            },
+            Some(frame) => frame
+        };
+        // interleave
+        self.pending.clear();
+        self.position = 0;
+        let block_size = frame.header.block_size;
+        for i in 0..block_size {
+            for block in frame.blocks.iter() {
+                self.pending.push(block[i]);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// fills fixed-size chunks of interleaved samples through a `SampleReader`
+pub struct ChunkedDecoder<'a> {
+    reader: SampleReader<'a>
+}
+
+impl<'a> ChunkedDecoder<'a> {
+    pub fn new(stream: &'a Stream, reader: &'a mut dyn Decode) -> Self {
+        ChunkedDecoder { reader: SampleReader::new(stream, reader) }
+    }
+
+    // see `SampleReader::read_samples`
+    pub fn fill(&mut self, out: &mut [i32]) -> Result<usize> {
+        self.reader.read_samples(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use super::super::bits::BitReader;
+    use super::super::decode::DecodingReadProxy;
+
+    static STEREO16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+
+    fn decode_interleaved() -> Vec<i32> {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut samples: Vec<i32> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            for i in 0..frame.header.block_size {
+                samples.push(frame.blocks[0][i]);
+                samples.push(frame.blocks[1][i]);
+            }
+        }).unwrap();
+        samples
+    }
+
+    fn decode_chunked(chunk_size: usize) -> Vec<i32> {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut decoder = ChunkedDecoder::new(&stream, &mut reader);
+        let mut samples: Vec<i32> = Vec::new();
+        let mut chunk = vec![0; chunk_size];
+        loop {
+            let n = decoder.fill(&mut chunk).unwrap();
+            samples.extend_from_slice(&chunk[..n]);
+            if n < chunk_size {
+                break;
+            }
+        }
+        assert_eq!(decoder.fill(&mut chunk).unwrap(), 0);
+        samples
+    }
+
+    #[test]
+    fn test_chunk_smaller_than_block() {
+        assert_eq!(decode_chunked(50), decode_interleaved());
+    }
+
+    #[test]
+    fn test_chunk_equal_to_block() {
+        assert_eq!(decode_chunked(128), decode_interleaved());
+    }
+
+    #[test]
+    fn test_chunk_splitting_channels() {
+        // chunks ending between the left and the right of a sample
+        assert_eq!(decode_chunked(1), decode_interleaved());
+        assert_eq!(decode_chunked(63), decode_interleaved());
+    }
+
+    #[test]
+    fn test_chunk_larger_than_block() {
+        let samples = decode_interleaved();
+        assert_eq!(samples.len(), 296 * 2);
+        assert_eq!(decode_chunked(300), samples);
+        assert_eq!(decode_chunked(1024), samples);
+    }
+
+    #[test]
+    fn test_read_samples() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut sample_reader = SampleReader::new(&stream, &mut reader);
+        let mut samples: Vec<i32> = Vec::new();
+        let mut chunk = [0; 100];
+        // fewer than requested only at the end of stream
+        for _ in 0..5 {
+            assert_eq!(sample_reader.read_samples(&mut chunk).unwrap(), 100);
+            samples.extend_from_slice(&chunk);
+        }
+        let n = sample_reader.read_samples(&mut chunk).unwrap();
+        assert_eq!(n, 296 * 2 - 500);
+        samples.extend_from_slice(&chunk[..n]);
+        assert_eq!(sample_reader.read_samples(&mut chunk).unwrap(), 0);
+        assert_eq!(samples, decode_interleaved());
+    }
+}
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 
 pub trait Hasher<T> {
     fn hash(&mut self, data: &[u8]);
@@ -10,6 +12,12 @@ pub struct HasherCrc8 {
     state: u8
 }
 
+impl Default for HasherCrc8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HasherCrc8 {
     pub fn new() -> Self {
         HasherCrc8 {
@@ -39,6 +47,12 @@ pub struct HasherCrc16Buypass {
     state: u16
 }
 
+impl Default for HasherCrc16Buypass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HasherCrc16Buypass {
     pub fn new() -> Self {
         HasherCrc16Buypass {
@@ -63,7 +77,142 @@ impl Hasher<u16> for HasherCrc16Buypass {
     }
 }
 
-static CRC8_TABLE: &'static [u8] = &[
+// CRC-32 of Ogg pages (polynomial = 0x04c11db7, initialized with 0, without reflection or final xor)
+pub struct HasherCrc32Ogg {
+    state: u32
+}
+
+impl Default for HasherCrc32Ogg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HasherCrc32Ogg {
+    pub fn new() -> Self {
+        HasherCrc32Ogg {
+            state: 0
+        }
+    }
+}
+
+impl Hasher<u32> for HasherCrc32Ogg {
+    fn hash(&mut self, data: &[u8]) {
+        self.state = data.iter().fold(self.state, |acc, &x| {
+            (acc << 8) ^ CRC32_TABLE[usize::from(((acc >> 24) as u8) ^ x)]
+        });
+    }
+
+    fn reset(&mut self) {
+        self.state = 0;
+    }
+
+    fn state(&self) -> u32 {
+        self.state
+    }
+}
+
+// MD5 (RFC 1321), the state being the digest of the data hashed so far
+#[derive(Clone)]
+pub struct HasherMd5 {
+    state: [u32; 4],
+    // pending bytes which do not fill a whole chunk yet
+    buffer: Vec<u8>,
+    length: u64
+}
+
+impl Default for HasherMd5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HasherMd5 {
+    const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    pub fn new() -> Self {
+        HasherMd5 {
+            state: HasherMd5::INITIAL_STATE,
+            buffer: Vec::with_capacity(64),
+            length: 0
+        }
+    }
+
+    fn process_chunk(state: &mut [u32; 4], chunk: &[u8]) {
+        let mut m = [0u32; 16];
+        for (i, word) in m.iter_mut().enumerate() {
+            *word = u32::from_le_bytes([chunk[i * 4], chunk[i * 4 + 1], chunk[i * 4 + 2], chunk[i * 4 + 3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = *state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16)
+            };
+            let f = f.wrapping_add(a)
+                .wrapping_add(MD5_K_TABLE[i])
+                .wrapping_add(m[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(MD5_S_TABLE[i]));
+        }
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+}
+
+impl Hasher<u128> for HasherMd5 {
+    fn hash(&mut self, data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        let mut data = data;
+        if !self.buffer.is_empty() {
+            let len = usize::min(64 - self.buffer.len(), data.len());
+            self.buffer.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.buffer.len() < 64 {
+                return
+            }
+            HasherMd5::process_chunk(&mut self.state, &self.buffer);
+            self.buffer.clear();
+        }
+        let mut chunks = data.chunks_exact(64);
+        for chunk in &mut chunks {
+            HasherMd5::process_chunk(&mut self.state, chunk);
+        }
+        self.buffer.extend_from_slice(chunks.remainder());
+    }
+
+    fn reset(&mut self) {
+        self.state = HasherMd5::INITIAL_STATE;
+        self.buffer.clear();
+        self.length = 0;
+    }
+
+    // pads a copy of the pending bytes so that hashing can continue afterwards
+    fn state(&self) -> u128 {
+        let mut state = self.state;
+        let mut tail = self.buffer.clone();
+        tail.push(0x80);
+        while tail.len() % 64 != 56 {
+            tail.push(0);
+        }
+        tail.extend_from_slice(&self.length.wrapping_mul(8).to_le_bytes());
+        for chunk in tail.chunks_exact(64) {
+            HasherMd5::process_chunk(&mut state, chunk);
+        }
+        // the digest bytes in order, as the signature of STREAMINFO is read
+        state.iter()
+            .flat_map(|x| x.to_le_bytes())
+            .fold(0u128, |acc, x| (acc << 8) | u128::from(x))
+    }
+}
+
+static CRC8_TABLE: &[u8] = &[
     0x00, 0x07, 0x0e, 0x09, 0x1c, 0x1b, 0x12, 0x15,
     0x38, 0x3f, 0x36, 0x31, 0x24, 0x23, 0x2a, 0x2d,
     0x70, 0x77, 0x7e, 0x79, 0x6c, 0x6b, 0x62, 0x65,
@@ -98,7 +247,7 @@ static CRC8_TABLE: &'static [u8] = &[
     0xe6, 0xe1, 0xe8, 0xef, 0xfa, 0xfd, 0xf4, 0xf3
 ];
 
-static CRC16_TABLE: &'static [u16] = &[
+static CRC16_TABLE: &[u16] = &[
     0x0000, 0x8005, 0x800f, 0x000a, 0x801b, 0x001e, 0x0014, 0x8011,
     0x8033, 0x0036, 0x003c, 0x8039, 0x0028, 0x802d, 0x8027, 0x0022,
     0x8063, 0x0066, 0x006c, 0x8069, 0x0078, 0x807d, 0x8077, 0x0072,
@@ -132,3 +281,163 @@ static CRC16_TABLE: &'static [u16] = &[
     0x0220, 0x8225, 0x822f, 0x022a, 0x823b, 0x023e, 0x0234, 0x8231,
     0x8213, 0x0216, 0x021c, 0x8219, 0x0208, 0x820d, 0x8207, 0x0202
 ];
+
+static CRC32_TABLE: &[u32] = &[
+    0x00000000, 0x04c11db7, 0x09823b6e, 0x0d4326d9, 0x130476dc, 0x17c56b6b, 0x1a864db2, 0x1e475005,
+    0x2608edb8, 0x22c9f00f, 0x2f8ad6d6, 0x2b4bcb61, 0x350c9b64, 0x31cd86d3, 0x3c8ea00a, 0x384fbdbd,
+    0x4c11db70, 0x48d0c6c7, 0x4593e01e, 0x4152fda9, 0x5f15adac, 0x5bd4b01b, 0x569796c2, 0x52568b75,
+    0x6a1936c8, 0x6ed82b7f, 0x639b0da6, 0x675a1011, 0x791d4014, 0x7ddc5da3, 0x709f7b7a, 0x745e66cd,
+    0x9823b6e0, 0x9ce2ab57, 0x91a18d8e, 0x95609039, 0x8b27c03c, 0x8fe6dd8b, 0x82a5fb52, 0x8664e6e5,
+    0xbe2b5b58, 0xbaea46ef, 0xb7a96036, 0xb3687d81, 0xad2f2d84, 0xa9ee3033, 0xa4ad16ea, 0xa06c0b5d,
+    0xd4326d90, 0xd0f37027, 0xddb056fe, 0xd9714b49, 0xc7361b4c, 0xc3f706fb, 0xceb42022, 0xca753d95,
+    0xf23a8028, 0xf6fb9d9f, 0xfbb8bb46, 0xff79a6f1, 0xe13ef6f4, 0xe5ffeb43, 0xe8bccd9a, 0xec7dd02d,
+    0x34867077, 0x30476dc0, 0x3d044b19, 0x39c556ae, 0x278206ab, 0x23431b1c, 0x2e003dc5, 0x2ac12072,
+    0x128e9dcf, 0x164f8078, 0x1b0ca6a1, 0x1fcdbb16, 0x018aeb13, 0x054bf6a4, 0x0808d07d, 0x0cc9cdca,
+    0x7897ab07, 0x7c56b6b0, 0x71159069, 0x75d48dde, 0x6b93dddb, 0x6f52c06c, 0x6211e6b5, 0x66d0fb02,
+    0x5e9f46bf, 0x5a5e5b08, 0x571d7dd1, 0x53dc6066, 0x4d9b3063, 0x495a2dd4, 0x44190b0d, 0x40d816ba,
+    0xaca5c697, 0xa864db20, 0xa527fdf9, 0xa1e6e04e, 0xbfa1b04b, 0xbb60adfc, 0xb6238b25, 0xb2e29692,
+    0x8aad2b2f, 0x8e6c3698, 0x832f1041, 0x87ee0df6, 0x99a95df3, 0x9d684044, 0x902b669d, 0x94ea7b2a,
+    0xe0b41de7, 0xe4750050, 0xe9362689, 0xedf73b3e, 0xf3b06b3b, 0xf771768c, 0xfa325055, 0xfef34de2,
+    0xc6bcf05f, 0xc27dede8, 0xcf3ecb31, 0xcbffd686, 0xd5b88683, 0xd1799b34, 0xdc3abded, 0xd8fba05a,
+    0x690ce0ee, 0x6dcdfd59, 0x608edb80, 0x644fc637, 0x7a089632, 0x7ec98b85, 0x738aad5c, 0x774bb0eb,
+    0x4f040d56, 0x4bc510e1, 0x46863638, 0x42472b8f, 0x5c007b8a, 0x58c1663d, 0x558240e4, 0x51435d53,
+    0x251d3b9e, 0x21dc2629, 0x2c9f00f0, 0x285e1d47, 0x36194d42, 0x32d850f5, 0x3f9b762c, 0x3b5a6b9b,
+    0x0315d626, 0x07d4cb91, 0x0a97ed48, 0x0e56f0ff, 0x1011a0fa, 0x14d0bd4d, 0x19939b94, 0x1d528623,
+    0xf12f560e, 0xf5ee4bb9, 0xf8ad6d60, 0xfc6c70d7, 0xe22b20d2, 0xe6ea3d65, 0xeba91bbc, 0xef68060b,
+    0xd727bbb6, 0xd3e6a601, 0xdea580d8, 0xda649d6f, 0xc423cd6a, 0xc0e2d0dd, 0xcda1f604, 0xc960ebb3,
+    0xbd3e8d7e, 0xb9ff90c9, 0xb4bcb610, 0xb07daba7, 0xae3afba2, 0xaafbe615, 0xa7b8c0cc, 0xa379dd7b,
+    0x9b3660c6, 0x9ff77d71, 0x92b45ba8, 0x9675461f, 0x8832161a, 0x8cf30bad, 0x81b02d74, 0x857130c3,
+    0x5d8a9099, 0x594b8d2e, 0x5408abf7, 0x50c9b640, 0x4e8ee645, 0x4a4ffbf2, 0x470cdd2b, 0x43cdc09c,
+    0x7b827d21, 0x7f436096, 0x7200464f, 0x76c15bf8, 0x68860bfd, 0x6c47164a, 0x61043093, 0x65c52d24,
+    0x119b4be9, 0x155a565e, 0x18197087, 0x1cd86d30, 0x029f3d35, 0x065e2082, 0x0b1d065b, 0x0fdc1bec,
+    0x3793a651, 0x3352bbe6, 0x3e119d3f, 0x3ad08088, 0x2497d08d, 0x2056cd3a, 0x2d15ebe3, 0x29d4f654,
+    0xc5a92679, 0xc1683bce, 0xcc2b1d17, 0xc8ea00a0, 0xd6ad50a5, 0xd26c4d12, 0xdf2f6bcb, 0xdbee767c,
+    0xe3a1cbc1, 0xe760d676, 0xea23f0af, 0xeee2ed18, 0xf0a5bd1d, 0xf464a0aa, 0xf9278673, 0xfde69bc4,
+    0x89b8fd09, 0x8d79e0be, 0x803ac667, 0x84fbdbd0, 0x9abc8bd5, 0x9e7d9662, 0x933eb0bb, 0x97ffad0c,
+    0xafb010b1, 0xab710d06, 0xa6322bdf, 0xa2f33668, 0xbcb4666d, 0xb8757bda, 0xb5365d03, 0xb1f740b4
+];
+
+static MD5_S_TABLE: &[u32] = &[
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21
+];
+
+static MD5_K_TABLE: &[u32] = &[
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee,
+    0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+    0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa,
+    0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+    0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05,
+    0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039,
+    0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+    0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn md5(data: &[u8]) -> u128 {
+        let mut hasher = HasherMd5::new();
+        hasher.hash(data);
+        hasher.state()
+    }
+
+    // the bitwise definitions which the tables are derived from
+    fn crc8_bitwise(data: &[u8]) -> u8 {
+        data.iter().fold(0u8, |mut acc, &x| {
+            acc ^= x;
+            for _ in 0..8 {
+                acc = if acc & 0x80 != 0 { (acc << 1) ^ 0x07 } else { acc << 1 };
+            }
+            acc
+        })
+    }
+
+    fn crc16_bitwise(data: &[u8]) -> u16 {
+        data.iter().fold(0u16, |mut acc, &x| {
+            acc ^= u16::from(x) << 8;
+            for _ in 0..8 {
+                acc = if acc & 0x8000 != 0 { (acc << 1) ^ 0x8005 } else { acc << 1 };
+            }
+            acc
+        })
+    }
+
+    // pseudo-random bytes from a linear congruential generator
+    fn random_bytes(seed: u32, len: usize) -> Vec<u8> {
+        let mut x = seed;
+        (0..len).map(|_| {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (x >> 16) as u8
+        }).collect()
+    }
+
+    #[test]
+    fn test_crc_check_values() {
+        let mut crc8 = HasherCrc8::new();
+        crc8.hash(b"123456789");
+        assert_eq!(crc8.state(), 0xf4);
+        let mut crc16 = HasherCrc16Buypass::new();
+        crc16.hash(b"123456789");
+        assert_eq!(crc16.state(), 0xfee8);
+    }
+
+    #[test]
+    fn test_crc_tables() {
+        for seed in 0..32 {
+            let data = random_bytes(seed, seed as usize * 37);
+            let mut crc8 = HasherCrc8::new();
+            let mut crc16 = HasherCrc16Buypass::new();
+            // in pieces of various sizes
+            for chunk in data.chunks(seed as usize + 1) {
+                crc8.hash(chunk);
+                crc16.hash(chunk);
+            }
+            assert_eq!(crc8.state(), crc8_bitwise(&data));
+            assert_eq!(crc16.state(), crc16_bitwise(&data));
+        }
+    }
+
+    #[test]
+    fn test_crc32_ogg() {
+        let mut hasher = HasherCrc32Ogg::new();
+        hasher.hash(b"123456789");
+        assert_eq!(hasher.state(), 0x89a1897f);
+        hasher.reset();
+        assert_eq!(hasher.state(), 0);
+    }
+
+    #[test]
+    fn test_md5() {
+        assert_eq!(md5(b""), 0xd41d8cd98f00b204e9800998ecf8427e);
+        assert_eq!(md5(b"abc"), 0x900150983cd24fb0d6963f7d28e17f72);
+        assert_eq!(md5(b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"),
+            0x57edf4a22be3c955ac49da2e2107b67a);
+    }
+
+    #[test]
+    fn test_md5_incremental() {
+        let data: Vec<u8> = (0..200).map(|x| x as u8).collect();
+        let mut hasher = HasherMd5::new();
+        for chunk in data.chunks(7) {
+            hasher.hash(chunk);
+        }
+        assert_eq!(hasher.state(), md5(&data));
+        // querying the state does not finish the hash
+        hasher.hash(b"abc");
+        assert_eq!(hasher.state(), md5(&[&data[..], b"abc"].concat()));
+        hasher.reset();
+        assert_eq!(hasher.state(), md5(b""));
+    }
+}
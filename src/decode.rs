@@ -1,11 +1,23 @@
 
-use std::io;
-use std::io::Read;
+use alloc::vec::Vec;
+use super::io;
+use super::io::Read;
+#[cfg(feature = "std")]
+use std::io::{Seek, SeekFrom};
 use super::crc::{Hasher, HasherCrc8, HasherCrc16Buypass};
 use super::bits::{BitRead, BitReader};
+use super::error::{Error, ErrorCode, Result};
 
 pub trait Decode: BitRead + DecodingRead {
-    fn decode_rice(&mut self, parameter: usize) -> io::Result<i32>;
+    fn decode_rice(&mut self, parameter: usize) -> Result<i32>;
+
+    // decodes Rice codes to fill the slice, all of the same parameter as in a partition
+    fn decode_rice_block(&mut self, parameter: usize, out: &mut [i32]) -> Result<()> {
+        for residual in out.iter_mut() {
+            *residual = self.decode_rice(parameter)?;
+        }
+        Ok(())
+    }
 }
 
 pub trait DecodingRead {
@@ -13,42 +25,157 @@ pub trait DecodingRead {
     fn compute_crc8_end(&mut self) -> u8;
     fn compute_crc16_begin(&mut self);
     fn compute_crc16_end(&mut self) -> u16;
+    fn byte_offset(&self) -> u64;
+    // returns the next byte without consuming it, or None at the end of stream
+    fn peek_u8(&mut self) -> io::Result<Option<u8>>;
+    // returns the next 2 bytes in big-endian without consuming them, or None if fewer remain
+    fn peek_u16(&mut self) -> io::Result<Option<u16>>;
+}
+
+// a source the proxy is able to reposition, see `DecodingReadProxy::seekable`
+#[cfg(feature = "std")]
+pub trait ReadSeek: Read + Seek {}
+
+#[cfg(feature = "std")]
+impl<T: Read + Seek> ReadSeek for T {}
+
+enum Source<'a> {
+    Read(&'a mut dyn Read),
+    #[cfg(feature = "std")]
+    Seek(&'a mut dyn ReadSeek)
+}
+
+impl<'a> Source<'a> {
+    fn reader(&mut self) -> &mut dyn Read {
+        match self {
+            Source::Read(reader) => &mut **reader,
+            #[cfg(feature = "std")]
+            Source::Seek(reader) => &mut **reader
+        }
+    }
 }
 
 pub struct DecodingReadProxy<'a> {
-    underlying: &'a mut Read,
+    underlying: Source<'a>,
     crc8: HasherCrc8,
     crc16: HasherCrc16Buypass,
     computing_crc8: bool,
-    computing_crc16: bool
+    computing_crc16: bool,
+    // whether the bytes read are hashed at all, see `set_crc_enabled`
+    crc_enabled: bool,
+    byte_offset: u64,
+    // bytes read from the source but not yet delivered, either peeked or
+    // received before the source would block
+    pending: Vec<u8>
 }
 
 impl<'a> DecodingReadProxy<'a> {
-    pub fn new(reader: &'a mut Read) -> Self {
+    pub fn new(reader: &'a mut dyn Read) -> Self {
+        DecodingReadProxy::from_source(Source::Read(reader), 0)
+    }
+
+    // counts from the offset, for a reader positioned midway in the stream
+    pub fn with_byte_offset(reader: &'a mut dyn Read, byte_offset: u64) -> Self {
+        DecodingReadProxy::from_source(Source::Read(reader), byte_offset)
+    }
+
+    // counts from the current position of the reader, which `seek_to` is able to move
+    #[cfg(feature = "std")]
+    pub fn seekable(reader: &'a mut dyn ReadSeek) -> io::Result<Self> {
+        let byte_offset = reader.stream_position()?;
+        Ok(DecodingReadProxy::from_source(Source::Seek(reader), byte_offset))
+    }
+
+    fn from_source(underlying: Source<'a>, byte_offset: u64) -> Self {
         DecodingReadProxy {
-            underlying: reader,
+            underlying,
             crc8: HasherCrc8::new(),
             crc16: HasherCrc16Buypass::new(),
             computing_crc8: false,
-            computing_crc16: false
+            computing_crc16: false,
+            crc_enabled: true,
+            byte_offset,
+            pending: Vec::new()
+        }
+    }
+
+    // skips hashing the bytes read even between `compute_crc*_begin` and `compute_crc*_end`,
+    // which then return meaningless values. only for decoding without `DecodeOptions::verify_crc`.
+    pub fn set_crc_enabled(&mut self, enabled: bool) {
+        self.crc_enabled = enabled;
+    }
+
+    // moves the reader to the byte offset from the start of the source, discarding the
+    // pending bytes. any bit reader over the proxy must be dropped beforehand, as the bits
+    // it has queued belong to the former position.
+    #[cfg(feature = "std")]
+    pub fn seek_to(&mut self, byte_offset: u64) -> io::Result<()> {
+        match self.underlying {
+            Source::Seek(ref mut reader) => reader.seek(SeekFrom::Start(byte_offset))?,
+            Source::Read(_) => return Err(io::Error::new(io::ErrorKind::Unsupported, "reader is not seekable"))
+        };
+        self.byte_offset = byte_offset;
+        self.pending.clear();
+        Ok(())
+    }
+
+    // reads ahead until the given number of bytes are pending, returning false at the end of stream
+    fn fill_pending(&mut self, n: usize) -> io::Result<bool> {
+        while self.pending.len() < n {
+            let mut buf = [0u8; 1];
+            match read_exact_retrying(self.underlying.reader(), &mut buf).1 {
+                Ok(()) => self.pending.push(buf[0]),
+                Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+                Err(e) => return Err(e)
+            }
         }
+        Ok(true)
+    }
+
+    // moves the pending bytes to the beginning of the buffer, returning the number of them
+    fn take_pending(&mut self, buf: &mut [u8]) -> usize {
+        let n = self.pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        n
     }
 }
 
 impl<'a> Read for DecodingReadProxy<'a> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.underlying.read(buf)
+        let n = match self.pending.is_empty() {
+            true => self.underlying.reader().read(buf)?,
+            false => self.take_pending(buf)
+        };
+        self.byte_offset += n as u64;
+        Ok(n)
     }
 
+    // on `WouldBlock`, the bytes received so far are kept for the next read, so that
+    // the same read is able to be retried once the source has more data
     fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
-        let result = self.underlying.read_exact(buf);
+        let start = match self.pending.is_empty() {
+            true => 0,
+            false => self.take_pending(buf)
+        };
+        let (filled, result) = read_exact_retrying(self.underlying.reader(), &mut buf[start..]);
+        if let Err(e) = result {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.pending.extend_from_slice(&buf[..start + filled]);
+            }
+            return Err(e)
+        }
+        self.byte_offset += buf.len() as u64;
+        if !self.crc_enabled {
+            return Ok(())
+        }
         if self.computing_crc8 {
             self.crc8.hash(buf);
         }
         if self.computing_crc16 {
             self.crc16.hash(buf);
         }
-        result
+        Ok(())
     }
 }
 
@@ -72,10 +199,74 @@ impl<'a> DecodingRead for DecodingReadProxy<'a> {
         self.computing_crc16 = false;
         self.crc16.state()
     }
+
+    fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    fn peek_u8(&mut self) -> io::Result<Option<u8>> {
+        if !self.fill_pending(1)? {
+            return Ok(None)
+        }
+        Ok(Some(self.pending[0]))
+    }
+
+    fn peek_u16(&mut self) -> io::Result<Option<u16>> {
+        if !self.fill_pending(2)? {
+            return Ok(None)
+        }
+        Ok(Some(u16::from_be_bytes([self.pending[0], self.pending[1]])))
+    }
+}
+
+// fills the buffer retrying on `Interrupted` regardless of how the underlying reader
+// implements `read_exact`, so that only a real end of stream surfaces as `UnexpectedEof`.
+// returns the number of bytes filled along with the result, which are partial on error.
+fn read_exact_retrying(reader: &mut dyn Read, buf: &mut [u8]) -> (usize, io::Result<()>) {
+    let mut filled: usize = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => return (filled, Err(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer"))),
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return (filled, Err(e))
+        }
+    }
+    (filled, Ok(()))
+}
+
+impl<T: DecodingRead + ?Sized> DecodingRead for &mut T {
+    fn compute_crc8_begin(&mut self) {
+        (**self).compute_crc8_begin()
+    }
+
+    fn compute_crc8_end(&mut self) -> u8 {
+        (**self).compute_crc8_end()
+    }
+
+    fn compute_crc16_begin(&mut self) {
+        (**self).compute_crc16_begin()
+    }
+
+    fn compute_crc16_end(&mut self) -> u16 {
+        (**self).compute_crc16_end()
+    }
+
+    fn byte_offset(&self) -> u64 {
+        (**self).byte_offset()
+    }
+
+    fn peek_u8(&mut self) -> io::Result<Option<u8>> {
+        (**self).peek_u8()
+    }
+
+    fn peek_u16(&mut self) -> io::Result<Option<u16>> {
+        (**self).peek_u16()
+    }
 }
 
 // BitReader Extension
-impl<'a, Source: DecodingRead> DecodingRead for BitReader<'a, Source> {
+impl<Source: DecodingRead> DecodingRead for BitReader<Source> {
     fn compute_crc8_begin(&mut self) {
         self.source.compute_crc8_begin()
     }
@@ -91,23 +282,99 @@ impl<'a, Source: DecodingRead> DecodingRead for BitReader<'a, Source> {
     fn compute_crc16_end(&mut self) -> u16 {
         self.source.compute_crc16_end()
     }
+
+    // NOTE: bytes partially consumed into the bit queue are counted as read
+    fn byte_offset(&self) -> u64 {
+        self.source.byte_offset()
+    }
+
+    // NOTE: the reader must be aligned to byte
+    fn peek_u8(&mut self) -> io::Result<Option<u8>> {
+        match self.queued_u8() {
+            Some(byte) => Ok(Some(byte)),
+            None => self.source.peek_u8()
+        }
+    }
+
+    // NOTE: the reader must be aligned to byte
+    fn peek_u16(&mut self) -> io::Result<Option<u16>> {
+        match (self.queued_u16(), self.queued_u8()) {
+            (Some(bytes), _) => Ok(Some(bytes)),
+            (None, Some(byte)) => Ok(self.source.peek_u8()?.map(|x| u16::from_be_bytes([byte, x]))),
+            (None, None) => self.source.peek_u16()
+        }
+    }
 }
 
-impl<'a, Source: Read + DecodingRead> Decode for BitReader<'a, Source> {
+impl<Source: Read + DecodingRead> Decode for BitReader<Source> {
     // Rice Decoding
-    fn decode_rice(&mut self, parameter: usize) -> io::Result<i32> {
+    fn decode_rice(&mut self, parameter: usize) -> Result<i32> {
         // unary decoding
-        let msb: u32 = self.read_unary()?;
+        let msb = read_rice_quotient(self, parameter)?;
         let lsb = self.read_u32_bits(parameter)?;
-        let v = ((msb << parameter) | lsb) as i32;
-        // convert to signed (zig-zag decoding)
-        Ok((v >> 1) ^ -(v & 1))
+        fold_rice(msb, lsb, parameter)
+    }
+
+    // takes the codes from the queue as long as it holds them whole, refilling it with
+    // the bytes the rest of the codes cover for certain, so that nothing past them is read
+    fn decode_rice_block(&mut self, parameter: usize, out: &mut [i32]) -> Result<()> {
+        let len = out.len();
+        for (i, residual) in out.iter_mut().enumerate() {
+            let (msb, lsb) = match self.read_queued_rice(parameter) {
+                Some(code) => code,
+                None => {
+                    // each code takes at least one bit besides the remainder
+                    self.prefetch((len - i) * (parameter + 1))?;
+                    match self.read_queued_rice(parameter) {
+                        Some(code) => code,
+                        None => {
+                            let msb = read_rice_quotient(self, parameter)?;
+                            (msb, self.read_u32_bits(parameter)?)
+                        }
+                    }
+                }
+            };
+            *residual = fold_rice(msb, lsb, parameter)?;
+        }
+        Ok(())
     }
 }
 
+// the longest run of 0 bits read as the quotient of a Rice code. a quotient of 2^24 takes
+// 2MiB for a single residual, which no encoder would choose over escaping the partition,
+// so a longer run means corruption such as a stream of zeros, without reading it through.
+const MAX_RICE_QUOTIENT: u32 = 1 << 24;
+
+#[inline]
+pub(super) fn rice_quotient_limit(parameter: usize) -> u32 {
+    // residuals fit in 32 bits as well
+    (u32::MAX >> parameter).min(MAX_RICE_QUOTIENT)
+}
+
+fn read_rice_quotient(reader: &mut dyn BitRead, parameter: usize) -> Result<u32> {
+    reader.read_unary_bounded(rice_quotient_limit(parameter))?
+        .ok_or_else(|| Error::from_code(ErrorCode::RiceUnaryTooLong))
+}
+
+#[inline]
+pub(super) fn fold_rice(msb: u32, lsb: u32, parameter: usize) -> Result<i32> {
+    // residuals fit in 32 bits, so a longer quotient means corruption
+    if msb > u32::MAX >> parameter {
+        return Err(Error::from_code(ErrorCode::RiceUnaryTooLong))
+    }
+    let v = (msb << parameter) | lsb;
+    // convert to signed (zig-zag decoding)
+    // NOTE: shift before casting so that the sign bit is not carried into the magnitude
+    Ok(((v >> 1) as i32) ^ -((v & 1) as i32))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+    use super::super::frame::Frame;
+    use super::super::stream::Stream;
+    use super::super::options::DecodeOptions;
 
     #[test]
     fn test_rice() {
@@ -125,4 +392,322 @@ mod tests {
         // -((9 << 2) + 1)
         assert_eq!(reader.decode_rice(2).unwrap(), -19);
     }
+
+    // codes the zig-zag folded values with the rice parameter
+    fn rice_bytes(values: &[u32], parameter: usize) -> Vec<u8> {
+        let mut bits: Vec<bool> = Vec::new();
+        for &v in values {
+            let msb = v >> parameter;
+            bits.extend((0..msb).map(|_| false));
+            bits.push(true);
+            bits.extend((0..parameter).rev().map(|i| (v >> i) & 1 == 1));
+        }
+        bits.chunks(8)
+            .map(|x| x.iter().enumerate().fold(0u8, |acc, (i, &b)| acc | ((b as u8) << (7 - i))))
+            .collect()
+    }
+
+    #[test]
+    fn test_rice_extremes() {
+        let values: &[u32] = &[0xffff_ffff, 0xffff_fffe, 0xffff_fffd, 0x8000_0000, 0x7fff_ffff, 1, 0];
+        let expected: &[i32] = &[i32::MIN, i32::MAX, -0x7fff_ffff, 0x4000_0000, -0x4000_0000, -1, 0];
+        for &parameter in &[14, 16, 24, 30] {
+            let bytes = rice_bytes(values, parameter);
+            let mut bytes: &[u8] = &bytes;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            for &x in expected {
+                assert_eq!(reader.decode_rice(parameter).unwrap(), x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rice_block() {
+        let values: Vec<u32> = (0..300u32).map(|x| (x * 7919) % 5000).collect();
+        for &parameter in &[0, 1, 4, 9, 14, 30] {
+            let values: Vec<u32> = values.iter().map(|&x| x << parameter.min(12)).collect();
+            let mut bytes = rice_bytes(&values, parameter);
+            // the footer-like bytes following the codes are not read ahead
+            let end = bytes.len() as u64;
+            bytes.extend_from_slice(&[0xab, 0xcd]);
+            let mut bytes: &[u8] = &bytes;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let mut residuals = vec![0i32; values.len()];
+            reader.decode_rice_block(parameter, &mut residuals).unwrap();
+            let expected: Vec<i32> = values.iter().map(|&v| ((v >> 1) as i32) ^ -((v & 1) as i32)).collect();
+            assert_eq!(residuals, expected);
+            reader.align_to_byte();
+            assert_eq!(reader.byte_offset(), end);
+            assert_eq!(reader.read_u16().unwrap(), 0xabcd);
+        }
+    }
+
+    #[test]
+    fn test_rice_unary_too_long() {
+        // the largest quotient with the parameter of 20 is 4095
+        let bytes = rice_bytes(&[0xffff_ffff], 20);
+        let mut bytes: &[u8] = &bytes;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        assert_eq!(reader.decode_rice(20).unwrap(), i32::MIN);
+        // a quotient of 4096 followed by the remainder
+        let mut bytes = vec![0u8; 512];
+        bytes.extend_from_slice(&[0xff, 0xff, 0xff]);
+        let mut bytes: &[u8] = &bytes;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        match reader.decode_rice(20).unwrap_err().code() {
+            ErrorCode::RiceUnaryTooLong => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    // an endless source of zeros
+    struct Zeros;
+
+    impl Read for Zeros {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            for byte in buf.iter_mut() {
+                *byte = 0;
+            }
+            Ok(buf.len())
+        }
+    }
+
+    #[test]
+    fn test_rice_unary_endless_zeros() {
+        let mut zeros = Zeros;
+        let mut proxy = DecodingReadProxy::new(&mut zeros);
+        let mut reader = BitReader::new(&mut proxy);
+        match reader.decode_rice(0).unwrap_err().code() {
+            ErrorCode::RiceUnaryTooLong => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        // gives up past 2^24 bits rather than reading up to the overflow
+        assert!(reader.byte_offset() <= (1 << 21) + 1);
+        let mut residuals = [0i32; 16];
+        match reader.decode_rice_block(4, &mut residuals).unwrap_err().code() {
+            ErrorCode::RiceUnaryTooLong => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        assert!(reader.byte_offset() <= (1 << 22) + 2);
+    }
+
+    #[test]
+    fn test_peek_u8() {
+        let mut bytes: &[u8] = &[0x12, 0x34, 0x56];
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        assert_eq!(reader.peek_u8().unwrap(), Some(0x12));
+        assert_eq!(reader.peek_u8().unwrap(), Some(0x12));
+        assert_eq!(reader.byte_offset(), 0);
+        reader.compute_crc8_begin();
+        assert_eq!(reader.read_u16().unwrap(), 0x1234);
+        // the peeked byte is hashed once consumed
+        assert_eq!(reader.compute_crc8_end(), 0xf1);
+        assert_eq!(reader.byte_offset(), 2);
+        assert_eq!(reader.peek_u8().unwrap(), Some(0x56));
+        assert_eq!(reader.read_u8().unwrap(), 0x56);
+        assert_eq!(reader.peek_u8().unwrap(), None);
+    }
+
+    #[test]
+    fn test_crc_disabled() {
+        let mut bytes: &[u8] = &[0x12, 0x34, 0x12, 0x34];
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        proxy.set_crc_enabled(false);
+        proxy.compute_crc8_begin();
+        proxy.compute_crc16_begin();
+        proxy.read_exact(&mut [0u8; 2]).unwrap();
+        assert_eq!(proxy.compute_crc8_end(), 0);
+        assert_eq!(proxy.compute_crc16_end(), 0);
+        assert_eq!(proxy.byte_offset(), 2);
+        proxy.set_crc_enabled(true);
+        proxy.compute_crc8_begin();
+        proxy.read_exact(&mut [0u8; 2]).unwrap();
+        assert_eq!(proxy.compute_crc8_end(), 0xf1);
+    }
+
+    #[test]
+    fn test_crc_disabled_decoding() {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        proxy.set_crc_enabled(false);
+        let mut reader = BitReader::new(&mut proxy);
+        let options = DecodeOptions::default().verify_crc(false);
+        let stream = Stream::with_options(&mut reader, options).unwrap();
+        let mut num_samples: usize = 0;
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            num_samples += frame.header.block_size;
+        }).unwrap();
+        assert_eq!(num_samples, stream.stream_info.total_samples);
+    }
+
+    #[test]
+    fn test_peek_u16() {
+        let mut bytes: &[u8] = &[0x12, 0x34, 0x56];
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        assert_eq!(reader.peek_u16().unwrap(), Some(0x1234));
+        assert_eq!(reader.peek_u8().unwrap(), Some(0x12));
+        reader.compute_crc8_begin();
+        assert_eq!(reader.read_u8().unwrap(), 0x12);
+        assert_eq!(reader.peek_u16().unwrap(), Some(0x3456));
+        assert_eq!(reader.read_u16().unwrap(), 0x3456);
+        // the peeked bytes are hashed once consumed
+        let mut hasher = HasherCrc8::new();
+        hasher.hash(&[0x12, 0x34, 0x56]);
+        assert_eq!(reader.compute_crc8_end(), hasher.state());
+        assert_eq!(reader.byte_offset(), 3);
+        assert_eq!(reader.peek_u16().unwrap(), None);
+    }
+
+    // yields a single byte per read, interrupting every other call
+    struct InterruptingReader<'a> {
+        bytes: &'a [u8],
+        interrupt: bool
+    }
+
+    impl<'a> Read for InterruptingReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"))
+            }
+            if self.bytes.is_empty() || buf.is_empty() {
+                return Ok(0)
+            }
+            buf[0] = self.bytes[0];
+            self.bytes = &self.bytes[1..];
+            Ok(1)
+        }
+
+        // does not retry on `Interrupted` unlike the default implementation
+        fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+            for byte in buf.iter_mut() {
+                let mut array = [0u8; 1];
+                if self.read(&mut array)? == 0 {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "eof"))
+                }
+                *byte = array[0];
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_interrupted_source() {
+        let mut source = InterruptingReader {
+            bytes: include_bytes!("../testdata/stereo16.flac"),
+            interrupt: false
+        };
+        let mut proxy = DecodingReadProxy::new(&mut source);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut num_samples: usize = 0;
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            num_samples += frame.header.block_size;
+        }).unwrap();
+        assert_eq!(num_samples, stream.stream_info.total_samples);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_seek_to() {
+        use std::io::Cursor;
+        let mut cursor = Cursor::new(&include_bytes!("../testdata/stereo16.flac")[..]);
+        cursor.set_position(42);
+        let mut proxy = DecodingReadProxy::seekable(&mut cursor).unwrap();
+        assert_eq!(proxy.byte_offset(), 42);
+        assert_eq!(proxy.peek_u8().unwrap(), Some(0xff));
+        // to the third frame
+        proxy.seek_to(382).unwrap();
+        assert_eq!(proxy.byte_offset(), 382);
+        let mut sync = [0u8; 2];
+        proxy.read_exact(&mut sync).unwrap();
+        assert_eq!(sync, [0xff, 0xf8]);
+        assert_eq!(proxy.byte_offset(), 384);
+        // back to the first
+        proxy.seek_to(42).unwrap();
+        let mut byte = [0u8; 1];
+        assert_eq!(proxy.read(&mut byte).unwrap(), 1);
+        assert_eq!((byte[0], proxy.byte_offset()), (0xff, 43));
+        // not without a seekable source
+        let mut bytes: &[u8] = &[0x12];
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        assert_eq!(proxy.seek_to(0).unwrap_err().kind(), io::ErrorKind::Unsupported);
+    }
+
+    // yields the bytes in chunks, as a non-blocking source of a live stream would,
+    // having no data on every other call
+    struct ChunkedSource<'a> {
+        bytes: &'a [u8],
+        chunk_size: usize,
+        blocking: bool
+    }
+
+    impl<'a> Read for ChunkedSource<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.blocking = !self.blocking;
+            if self.blocking && !self.bytes.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::WouldBlock, "would block"))
+            }
+            let n = self.chunk_size.min(self.bytes.len()).min(buf.len());
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_would_block_source() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut source = ChunkedSource { bytes: &data, chunk_size: 3, blocking: false };
+        let mut proxy = DecodingReadProxy::new(&mut source);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut values: Vec<u64> = Vec::new();
+        let mut retries: usize = 0;
+        // reads of 40 bits spanning the chunks, each retried until the source has the data
+        while values.len() < 51 {
+            match reader.read_u64_bits(40) {
+                Ok(value) => values.push(value),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => retries += 1,
+                Err(e) => panic!("unexpected error {:?}", e)
+            }
+        }
+        assert!(retries > 51);
+        assert_eq!(reader.byte_offset(), 255);
+        let expected: Vec<u64> = data.chunks(5)
+            .take(51)
+            .map(|x| x.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+            .collect();
+        assert_eq!(values, expected);
+    }
+
+    // the stream along with the reader owning the proxy, as kept in a struct
+    struct OwningDecoder<'a> {
+        stream: Stream,
+        reader: BitReader<DecodingReadProxy<'a>>
+    }
+
+    fn open_owning<'a>(source: &'a mut dyn Read) -> Result<OwningDecoder<'a>> {
+        let mut reader = BitReader::from_owned(DecodingReadProxy::new(source));
+        let stream = Stream::new(&mut reader)?;
+        Ok(OwningDecoder { stream, reader })
+    }
+
+    #[test]
+    fn test_owned_reader() {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut decoder = open_owning(&mut bytes).unwrap();
+        let mut num_samples: usize = 0;
+        decoder.stream.decode_frames(&mut decoder.reader, |frame: &Frame| {
+            num_samples += frame.header.block_size;
+        }).unwrap();
+        assert_eq!(num_samples, decoder.stream.stream_info.total_samples);
+        let proxy = decoder.reader.into_inner();
+        assert_eq!(proxy.byte_offset(), 909);
+    }
 }
@@ -0,0 +1,107 @@
+use std::ops::Deref;
+
+// gains applied when folding surround channels into stereo.
+// the defaults follow ITU-R BS.775 with the LFE channel discarded.
+#[derive(Debug, Clone, Copy)]
+pub struct DownmixCoefficients {
+    pub center: f64,
+    pub surround: f64,
+    pub lfe: f64,
+    // scales the gains so that the sum for each output is at most 1, preventing clipping
+    pub normalize: bool
+}
+
+impl Default for DownmixCoefficients {
+    fn default() -> Self {
+        DownmixCoefficients {
+            center: std::f64::consts::FRAC_1_SQRT_2,
+            surround: std::f64::consts::FRAC_1_SQRT_2,
+            lfe: 0.0,
+            normalize: true
+        }
+    }
+}
+
+impl DownmixCoefficients {
+    // the (left, right) gains of each channel in the FLAC channel order
+    pub fn gains(&self, num_channels: usize) -> Option<Vec<(f64, f64)>> {
+        let front_left = (1.0, 0.0);
+        let front_right = (0.0, 1.0);
+        let center = (self.center, self.center);
+        let lfe = (self.lfe, self.lfe);
+        let surround_left = (self.surround, 0.0);
+        let surround_right = (0.0, self.surround);
+        let surround_center = (self.surround, self.surround);
+        let mut gains = match num_channels {
+            1 => vec![(1.0, 1.0)],
+            2 => vec![front_left, front_right],
+            3 => vec![front_left, front_right, center],
+            4 => vec![front_left, front_right, surround_left, surround_right],
+            5 => vec![front_left, front_right, center, surround_left, surround_right],
+            6 => vec![front_left, front_right, center, lfe, surround_left, surround_right],
+            7 => vec![front_left, front_right, center, lfe, surround_center, surround_left, surround_right],
+            8 => vec![front_left, front_right, center, lfe, surround_left, surround_right, surround_left, surround_right],
+            _ => return None
+        };
+        if self.normalize {
+            let left: f64 = gains.iter().map(|x| x.0).sum();
+            let right: f64 = gains.iter().map(|x| x.1).sum();
+            let scale = left.max(right).max(1.0);
+            for gain in &mut gains {
+                gain.0 /= scale;
+                gain.1 /= scale;
+            }
+        }
+        Some(gains)
+    }
+}
+
+// mixes a block of each channel into stereo, clamping to the range of `bits_per_sample`
+pub fn mix<B>(gains: &[(f64, f64)], blocks: &[B], bits_per_sample: usize, left: &mut Vec<i32>, right: &mut Vec<i32>)
+    where B: Deref<Target = [i32]> {
+    let max = ((1i64 << (bits_per_sample - 1)) - 1) as f64;
+    let min = -(1i64 << (bits_per_sample - 1)) as f64;
+    let block_size = blocks.first().map_or(0, |x| x.len());
+    for i in 0..block_size {
+        let mut l: f64 = 0.0;
+        let mut r: f64 = 0.0;
+        for (gain, block) in gains.iter().zip(blocks) {
+            let sample = block[i] as f64;
+            l += sample * gain.0;
+            r += sample * gain.1;
+        }
+        left.push(l.round().clamp(min, max) as i32);
+        right.push(r.round().clamp(min, max) as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gains() {
+        let coefficients = DownmixCoefficients::default();
+        assert_eq!(coefficients.gains(2), Some(vec![(1.0, 0.0), (0.0, 1.0)]));
+        assert_eq!(coefficients.gains(9), None);
+        let gains = coefficients.gains(6).unwrap();
+        assert_eq!(gains.len(), 6);
+        // 1 + 0.707 + 0.707
+        let sum: f64 = gains.iter().map(|x| x.0).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+        assert_eq!(gains[3], (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_mix() {
+        let coefficients = DownmixCoefficients { normalize: false, ..DownmixCoefficients::default() };
+        let gains = coefficients.gains(6).unwrap();
+        // FL, FR, FC, LFE, BL, BR
+        let blocks = vec![vec![100, 30000], vec![-100, 0], vec![1000, 30000], vec![5000, 0], vec![0, 30000], vec![10, 0]];
+        let mut left: Vec<i32> = Vec::new();
+        let mut right: Vec<i32> = Vec::new();
+        mix(&gains, &blocks, 16, &mut left, &mut right);
+        assert_eq!(left, vec![807, 32767]);
+        assert_eq!(right, vec![614, 21213]);
+    }
+}
@@ -1,6 +1,8 @@
 
-use std::io;
-use std::result;
+use alloc::boxed::Box;
+use core::fmt;
+use core::result;
+use super::io;
 
 pub type Result<T> = result::Result<T, Error>;
 
@@ -9,6 +11,10 @@ pub enum ErrorCode {
     Io(io::Error),
     WrongMagic,
     InvalidMetadataType,
+    InvalidBitDepth,
+    InvalidStreamInfo,
+    UnsupportedStreamFormat,
+    MetadataLengthMismatch,
 
     FrameOutOfSync,
     FrameHeaderCrcMismatch,
@@ -16,17 +22,31 @@ pub enum ErrorCode {
     FrameBlockSizeUnknown,
     FrameChannelAssignmentUnknown,
     FrameCrcMismatch,
+    FrameNumberInvalid,
+    FrameSampleRateMismatch,
+    FrameBlockSizeMismatch,
+    FrameChannelCountMismatch,
+    FrameBlockSizeExceedsMax,
+    FrameSampleCountMismatch,
 
     SubframeReservedType,
     SubframeOutOfSync,
+    SubframeWastedBitsInvalid,
+    SubframeOrderExceedsBlockSize,
 
     ResidualCodingMethodUnknown,
+    ResidualPartitionMismatch,
+    RiceUnaryTooLong,
 
     FixedLPCCoefficientUnknown,
     QLPPrecisionInvalid,
     LPCSignalRestoreFailure,
 
-    FrameBufferUnallocated
+    FrameBufferUnallocated,
+    SampleTypeTooNarrow,
+    InvalidArgument,
+
+    Md5Mismatch
 }
 
 #[derive(Debug)]
@@ -48,4 +68,94 @@ impl Error {
             u: Box::new(code)
         }
     }
+
+    pub fn code(&self) -> &ErrorCode {
+        &self.u
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            ErrorCode::Io(e) => return write!(f, "I/O error: {}", e),
+            ErrorCode::WrongMagic => "not a FLAC stream",
+            ErrorCode::InvalidMetadataType => "invalid metadata block type",
+            ErrorCode::InvalidBitDepth => "invalid bit depth",
+            ErrorCode::InvalidStreamInfo => "invalid STREAMINFO",
+            ErrorCode::UnsupportedStreamFormat => "unsupported stream format",
+            ErrorCode::MetadataLengthMismatch => "metadata block length mismatch",
+            ErrorCode::FrameOutOfSync => "frame out of sync",
+            ErrorCode::FrameHeaderCrcMismatch => "frame header CRC-8 mismatch",
+            ErrorCode::FrameSampleSizeUnknown => "unknown sample size in frame header",
+            ErrorCode::FrameBlockSizeUnknown => "unknown block size in frame header",
+            ErrorCode::FrameChannelAssignmentUnknown => "unknown channel assignment in frame header",
+            ErrorCode::FrameCrcMismatch => "frame CRC-16 mismatch",
+            ErrorCode::FrameNumberInvalid => "invalid frame or sample number in frame header",
+            ErrorCode::FrameSampleRateMismatch => "frame sample rate differs from STREAMINFO",
+            ErrorCode::FrameBlockSizeMismatch => "frame block size inconsistent with the blocking strategy",
+            ErrorCode::FrameChannelCountMismatch => "frame number of channels differs from STREAMINFO",
+            ErrorCode::FrameBlockSizeExceedsMax => "frame block size exceeds the maximum of STREAMINFO",
+            ErrorCode::FrameSampleCountMismatch => "samples decoded differ from the frame block size",
+            ErrorCode::SubframeReservedType => "reserved subframe type",
+            ErrorCode::SubframeOutOfSync => "subframe out of sync",
+            ErrorCode::SubframeWastedBitsInvalid => "wasted bits exceed the subframe sample size",
+            ErrorCode::SubframeOrderExceedsBlockSize => "predictor order exceeds the block size",
+            ErrorCode::ResidualCodingMethodUnknown => "unknown residual coding method",
+            ErrorCode::ResidualPartitionMismatch => "residual partitions do not fit the block size",
+            ErrorCode::RiceUnaryTooLong => "Rice-coded residual too large",
+            ErrorCode::FixedLPCCoefficientUnknown => "unknown fixed predictor order",
+            ErrorCode::QLPPrecisionInvalid => "invalid quantized LPC coefficient precision",
+            ErrorCode::LPCSignalRestoreFailure => "failed to restore LPC signal",
+            ErrorCode::FrameBufferUnallocated => "frame buffer not allocated for every channel",
+            ErrorCode::SampleTypeTooNarrow => "sample type too narrow for the bit depth",
+            ErrorCode::InvalidArgument => "invalid argument",
+            ErrorCode::Md5Mismatch => "MD5 signature mismatch"
+        };
+        f.write_str(message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.u.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.code() {
+            ErrorCode::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_display() {
+        let error = Error::from_code(ErrorCode::FrameCrcMismatch);
+        assert_eq!(error.to_string(), "frame CRC-16 mismatch");
+        let error = Error::from(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer"));
+        assert_eq!(error.to_string(), "I/O error: failed to fill whole buffer");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_source() {
+        use std::error::Error as _;
+        let error = Error::from(io::Error::new(io::ErrorKind::InvalidData, "invalid"));
+        let source = error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::InvalidData);
+        assert!(Error::from_code(ErrorCode::Md5Mismatch).source().is_none());
+        // usable with `?` returning a boxed error
+        let boxed = || -> std::result::Result<(), Box<dyn std::error::Error>> {
+            Err(Error::from_code(ErrorCode::WrongMagic))?
+        };
+        assert_eq!(boxed().unwrap_err().to_string(), "not a FLAC stream");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::io::{BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+use super::error::{Error, Result};
+use super::bits::BitReader;
+use super::decode::{Decode, DecodingReadProxy};
+use super::frame::{Frame, FrameSink, FrameSizeSummary};
+use super::metadata::StreamInfo;
+use super::options::DecodeOptions;
+use super::sample::{FromSample, Sample};
+use super::stream::Stream;
+
+// owns a file along with the stream read from it, sparing the readers from being
+// assembled by hand
+pub struct FlacReader {
+    stream: Stream,
+    file: BufReader<File>
+}
+
+impl FlacReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        FlacReader::open_with_options(path, DecodeOptions::default())
+    }
+
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: DecodeOptions) -> Result<Self> {
+        let file = File::open(path)?;
+        let mut file = BufReader::new(file);
+        let stream = {
+            let mut proxy = DecodingReadProxy::new(&mut file);
+            let mut reader = BitReader::new(&mut proxy);
+            Stream::with_options(&mut reader, options)?
+        };
+        // the readers may have looked ahead of the first frame
+        file.seek(SeekFrom::Start(stream.first_frame_offset()))?;
+        Ok(FlacReader { stream, file })
+    }
+
+    pub fn stream(&self) -> &Stream {
+        &self.stream
+    }
+
+    pub fn stream_info(&self) -> &StreamInfo {
+        &self.stream.stream_info
+    }
+
+    // calls back with the stream and a reader at the current position of the file,
+    // with which any of the decoding methods of `Stream` can be used
+    pub fn with_reader<T, F>(&mut self, f: F) -> Result<T>
+        where F: FnOnce(&Stream, &mut dyn Decode) -> Result<T> {
+        let mut proxy = DecodingReadProxy::seekable(&mut self.file)?;
+        proxy.set_crc_enabled(self.stream.options.verify_crc);
+        let mut reader = BitReader::new(&mut proxy);
+        f(&self.stream, &mut reader)
+    }
+
+    pub fn decode_frames<S, F>(&mut self, sink: F) -> Result<FrameSizeSummary>
+        where S: Sample, F: FnMut(&Frame<S>) {
+        self.with_reader(|stream, reader| stream.decode_frames(reader, sink))
+    }
+
+    // see `Stream::try_decode_frames`
+    pub fn try_decode_frames<S, K>(&mut self, sink: K) -> core::result::Result<FrameSizeSummary, K::Error>
+        where S: Sample, K: FrameSink<S> {
+        let mut proxy = DecodingReadProxy::seekable(&mut self.file).map_err(Error::from)?;
+        proxy.set_crc_enabled(self.stream.options.verify_crc);
+        let mut reader = BitReader::new(&mut proxy);
+        self.stream.try_decode_frames(&mut reader, sink)
+    }
+
+    // see `Stream::seek`
+    pub fn seek(&mut self, target_sample: u64) -> Result<u64> {
+        self.stream.seek(&mut self.file, target_sample)
+    }
+}
+
+// decodes a whole file into interleaved 16-bit samples, returning them along with
+// the sample rate and the number of channels. other bit depths are scaled to 16 bits,
+// dropping the least significant bits of deeper samples.
+pub fn decode_file_i16<P: AsRef<Path>>(path: P) -> Result<(Vec<i16>, u32, u16)> {
+    let mut flac = FlacReader::open(path)?;
+    let info = *flac.stream_info();
+    let mut samples: Vec<i16> = Vec::with_capacity(info.reserved_samples() * info.number_of_channels);
+    flac.decode_frames(|frame: &Frame| {
+        for i in 0..frame.header.block_size {
+            for block in frame.blocks.iter() {
+                samples.push(i16::from_sample(block[i] as i64, info.bits_per_sample));
+            }
+        }
+    })?;
+    Ok((samples, info.sample_rate as u32, info.number_of_channels as u16))
+}
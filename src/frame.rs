@@ -1,18 +1,286 @@
 
-use std::io;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+use super::io;
 use super::error::{Error, ErrorCode, Result};
 use super::metadata::StreamInfo;
+use super::options::DecodeOptions;
 use super::decode::Decode;
+use super::sample::{FromSample, Sample, SampleSink};
+use super::crc::{Hasher, HasherMd5};
 
-pub struct Frame<'a> {
+pub struct Frame<'a, S: Sample = i32> {
     pub header: FrameHeader,
-    pub blocks: &'a mut Vec<Vec<i32>>
+    pub stats: FrameStats,
+    pub footer: FrameFooter,
+    pub blocks: &'a mut Vec<FrameBuffer<S>>
 }
 
-impl<'a> Frame<'a> {
-    pub fn from_reader(reader: &mut Decode, stream_info: &StreamInfo, blocks: &'a mut Vec<Vec<i32>>) -> Result<Option<Self>> {
-        reader.compute_crc16_begin();
-        let header = match FrameHeader::from_reader(reader, stream_info)? {
+// a block of samples reused across frames. resetting only rewinds the write cursor,
+// leaving the samples in place to be overwritten by the next frame.
+#[derive(Debug, Clone, Default)]
+pub struct FrameBuffer<S: Sample = i32> {
+    samples: Vec<S>,
+    len: usize
+}
+
+impl<S: Sample> FrameBuffer<S> {
+    pub fn new() -> Self {
+        FrameBuffer {
+            samples: Vec::new(),
+            len: 0
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        FrameBuffer {
+            samples: Vec::with_capacity(capacity),
+            len: 0
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    // advances the cursor by `n`, returning the samples passed over which hold stale values
+    fn extend(&mut self, n: usize) -> &mut [S] {
+        let offset = self.len;
+        self.len += n;
+        if self.samples.len() < self.len {
+            self.samples.resize(self.len, S::default());
+        }
+        &mut self.samples[offset..self.len]
+    }
+
+    // advances the cursor by `n`, filling the samples in order with the values read. the stale
+    // ones are overwritten and the rest appended, rather than zero-filled first only to be
+    // overwritten. on an error, the cursor stays where it was.
+    fn extend_with<F>(&mut self, n: usize, mut read: F) -> Result<()>
+        where F: FnMut() -> Result<S> {
+        let end = self.len + n;
+        let stale = self.samples.len().min(end);
+        for sample in self.samples[self.len.min(stale)..stale].iter_mut() {
+            *sample = read()?;
+        }
+        self.samples.reserve(end - stale);
+        for _ in stale..end {
+            self.samples.push(read()?);
+        }
+        self.len = end;
+        Ok(())
+    }
+
+    // advances the cursor past the samples given, the same way as `extend_with`
+    fn append<I: Iterator<Item = S>>(&mut self, mut samples: I) {
+        let mut n: usize = 0;
+        for (slot, sample) in self.samples[self.len..].iter_mut().zip(&mut samples) {
+            *slot = sample;
+            n += 1;
+        }
+        self.len += n;
+        if self.len == self.samples.len() {
+            self.samples.extend(samples);
+            self.len = self.samples.len();
+        }
+    }
+
+    #[cfg(test)]
+    fn push(&mut self, sample: S) {
+        if self.len < self.samples.len() {
+            self.samples[self.len] = sample;
+        } else {
+            self.samples.push(sample);
+        }
+        self.len += 1;
+    }
+}
+
+impl<S: Sample> Deref for FrameBuffer<S> {
+    type Target = [S];
+
+    fn deref(&self) -> &[S] {
+        &self.samples[..self.len]
+    }
+}
+
+impl<S: Sample> DerefMut for FrameBuffer<S> {
+    fn deref_mut(&mut self) -> &mut [S] {
+        &mut self.samples[..self.len]
+    }
+}
+
+// storage a frame needs only while being decoded, kept across frames not to allocate it for
+// every one of them
+#[derive(Debug, Clone, Default)]
+pub struct FrameScratch<S: Sample = i32> {
+    // the side channel whose extra bit does not fit in `S`
+    wide: FrameBuffer<S::Wide>,
+    // the storage of `FrameStats::subframes` given back by `recycle`
+    subframes: Vec<SubframeStats>
+}
+
+impl<S: Sample> FrameScratch<S> {
+    pub fn new() -> Self {
+        FrameScratch {
+            wide: FrameBuffer::new(),
+            subframes: Vec::new()
+        }
+    }
+
+    // `wide_capacity` is the block size of side channels not fitting in `S`, or 0 if they do
+    pub fn with_capacity(wide_capacity: usize, num_channels: usize) -> Self {
+        FrameScratch {
+            wide: FrameBuffer::with_capacity(wide_capacity),
+            subframes: Vec::with_capacity(num_channels)
+        }
+    }
+
+    // takes back the storage of the stats of a frame done with, for the next frame to reuse
+    pub fn recycle(&mut self, stats: FrameStats) {
+        self.subframes = stats.subframes;
+    }
+}
+
+impl<'b, S: Sample> IntoIterator for &'b FrameBuffer<S> {
+    type Item = &'b S;
+    type IntoIter = core::slice::Iter<'b, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// figures about how a frame is coded in the stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameStats {
+    // offset of the frame header from the beginning of the stream
+    pub byte_offset: u64,
+    // size of the frame including the header and footer
+    pub byte_len: u64,
+    // block size times the number of channels
+    pub num_samples: usize,
+    // in the coded order, e.g. left and side for left-side stereo
+    pub subframes: Vec<SubframeStats>
+}
+
+impl FrameStats {
+    // the average number of coded bits per sample, which grows with the entropy of the signal
+    pub fn bits_per_sample(&self) -> f64 {
+        (self.byte_len * 8) as f64 / self.num_samples as f64
+    }
+
+    // digital silence, which decorrelates to zeros in every channel as well
+    pub fn is_silent(&self) -> bool {
+        self.subframes.iter().all(|x| x.constant == Some(0))
+    }
+}
+
+// receives the frames decoded, failing with an error that aborts decoding, see
+// `Stream::try_decode_frames`. implemented for closures returning a `Result`.
+pub trait FrameSink<S: Sample> {
+    // holds the errors of decoding besides those of the sink
+    type Error: From<Error>;
+
+    fn write_frame(&mut self, frame: &Frame<S>) -> core::result::Result<(), Self::Error>;
+}
+
+impl<S, E, F> FrameSink<S> for F
+    where S: Sample, E: From<Error>, F: FnMut(&Frame<S>) -> core::result::Result<(), E> {
+    type Error = E;
+
+    fn write_frame(&mut self, frame: &Frame<S>) -> core::result::Result<(), E> {
+        self(frame)
+    }
+}
+
+// aggregates the sizes of the frames decoded, to be compared with those of STREAMINFO
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameSizeSummary {
+    pub num_frames: usize,
+    pub min_frame_size: u64,
+    pub max_frame_size: u64,
+    pub total_size: u64
+}
+
+impl FrameSizeSummary {
+    pub fn add(&mut self, stats: &FrameStats) {
+        let size = stats.byte_len;
+        if self.num_frames == 0 || size < self.min_frame_size {
+            self.min_frame_size = size;
+        }
+        self.max_frame_size = self.max_frame_size.max(size);
+        self.total_size += size;
+        self.num_frames += 1;
+    }
+
+    pub fn average_frame_size(&self) -> Option<f64> {
+        if self.num_frames == 0 {
+            return None
+        }
+        Some(self.total_size as f64 / self.num_frames as f64)
+    }
+
+    // whether the frames are within the sizes STREAMINFO declares, where 0 means unknown
+    pub fn conforms_to(&self, stream_info: &StreamInfo) -> bool {
+        let min = stream_info.min_frame_size as u64;
+        let max = stream_info.max_frame_size as u64;
+        self.num_frames == 0 || ((min == 0 || min <= self.min_frame_size) && (max == 0 || self.max_frame_size <= max))
+    }
+
+    // fills the frame sizes of STREAMINFO with those observed, e.g. to repair the ones left
+    // unknown by an encoder. nothing changes unless a frame has been decoded.
+    pub fn update_stream_info(&self, stream_info: &mut StreamInfo) {
+        if self.num_frames == 0 {
+            return
+        }
+        stream_info.min_frame_size = self.min_frame_size as usize;
+        stream_info.max_frame_size = self.max_frame_size as usize;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubframeStats {
+    pub method: PredictionMethod,
+    // the value repeated throughout the block of a constant subframe
+    pub constant: Option<i64>,
+    // the number of leading samples coded directly, which the predictor starts from
+    pub warm_up: usize,
+    // the zeros dropped from the least significant bits of every sample
+    pub wasted_bits: usize,
+    // how the residuals of a predicted subframe are coded, only with `DecodeOptions::inspect_residuals`
+    pub residual: Option<ResidualStats>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResidualStats {
+    // the bits of the Rice parameters, 4 or 5 by the coding method
+    pub parameter_bits: usize,
+    // the block is divided into 2^order partitions
+    pub partition_order: usize,
+    pub partitions: Vec<ResidualPartition>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResidualPartition {
+    // coded with the Rice parameter
+    Rice(u8),
+    // unencoded in the number of bits
+    Escaped(u8)
+}
+
+impl<'a, S: Sample> Frame<'a, S> {
+    pub fn from_reader(reader: &mut dyn Decode, stream_info: &StreamInfo, options: &DecodeOptions, blocks: &'a mut Vec<FrameBuffer<S>>) -> Result<Option<Self>> {
+        Frame::from_reader_with_scratch(reader, stream_info, options, blocks, &mut FrameScratch::new())
+    }
+
+    // same as `from_reader`, reusing the scratch of the previous frame
+    pub fn from_reader_with_scratch(reader: &mut dyn Decode, stream_info: &StreamInfo, options: &DecodeOptions, blocks: &'a mut Vec<FrameBuffer<S>>, scratch: &mut FrameScratch<S>) -> Result<Option<Self>> {
+        let byte_offset = reader.byte_offset();
+        if options.verify_crc {
+            reader.compute_crc16_begin();
+        }
+        let header = match FrameHeader::from_reader(reader, stream_info, options)? {
             None => {
                 // reached the end of file
                 reader.compute_crc16_end();
@@ -20,85 +288,288 @@ impl<'a> Frame<'a> {
             },
             Some(header) => header
         };
+        // the side channel takes an extra bit which is decoded into the wider type
+        if header.sample_size > S::BITS || header.sample_size + 1 > S::Wide::BITS {
+            return Err(Error::from_code(ErrorCode::SampleTypeTooNarrow))
+        }
+        for block in blocks.iter_mut() {
+            block.reset();
+        }
+        let mut subframes: Vec<SubframeStats> = core::mem::take(&mut scratch.subframes);
+        subframes.clear();
+        subframes.reserve(header.channel_assignment.num_channels());
         // NOTE: bps varies by channel assignment
         match header.channel_assignment {
+            #[cfg(not(feature = "parallel"))]
             ChannelAssignment::Independent(num_channels) => {
                 for i in 0..num_channels {
                     let block = blocks.get_mut(i)
                         .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
-                    let subframe = Subframe::from_reader(reader, header.sample_size, header.block_size)?;
-                    subframe.decode(reader, block)?;
+                    let subframe = Subframe::from_reader(reader, header.sample_size, header.block_size, options.inspect_residuals)?;
+                    let remainder = subframe.read(reader, block)?;
+                    let stats = if options.decodes_channel(i) {
+                        subframe.restore(remainder, block)?
+                    } else {
+                        subframe.skip(remainder, block)
+                    };
+                    subframes.push(stats);
                 }
             },
-            ChannelAssignment::LeftSideStereo => {
-                let (left_vec, tail) = blocks.split_first_mut()
-                    .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
-                let side_vec = tail.first_mut()
+            // the subframes are read one after another, then the signals of the channels
+            // are restored in parallel as they no longer depend on each other
+            #[cfg(feature = "parallel")]
+            ChannelAssignment::Independent(num_channels) => {
+                use rayon::prelude::*;
+                let blocks = blocks.get_mut(..num_channels)
                     .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
-                let left = Subframe::from_reader(reader, header.sample_size, header.block_size)?;
-                left.decode(reader, left_vec)?;
-                let side = Subframe::from_reader(reader, header.sample_size + 1, header.block_size)?;
-                side.decode(reader, side_vec)?;
-                // correlate
-                for (left, side) in left_vec.iter_mut().zip(side_vec) {
-                    *side = *left - *side;
+                let mut pending: Vec<(Subframe, SubframeRemainder)> = Vec::with_capacity(num_channels);
+                for block in blocks.iter_mut() {
+                    let subframe = Subframe::from_reader(reader, header.sample_size, header.block_size, options.inspect_residuals)?;
+                    let remainder = subframe.read(reader, block)?;
+                    pending.push((subframe, remainder));
+                }
+                let restored: Vec<Result<SubframeStats>> = blocks.par_iter_mut()
+                    .zip(pending.into_par_iter())
+                    .enumerate()
+                    .map(|(i, (block, (subframe, remainder)))| match options.decodes_channel(i) {
+                        true => subframe.restore(remainder, block),
+                        false => Ok(subframe.skip(remainder, block))
+                    })
+                    .collect();
+                for stats in restored {
+                    subframes.push(stats?);
                 }
             },
-            ChannelAssignment::SideRightStereo => {
-                let (side_vec, tail) = blocks.split_first_mut()
+            assignment => {
+                let (left_vec, tail) = blocks.split_first_mut()
                     .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
                 let right_vec = tail.first_mut()
                     .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
-                let side = Subframe::from_reader(reader, header.sample_size + 1, header.block_size)?;
-                side.decode(reader, side_vec)?;
-                let right = Subframe::from_reader(reader, header.sample_size, header.block_size)?;
-                right.decode(reader, right_vec)?;
-                // correlate
-                for (side, right) in side_vec.iter_mut().zip(right_vec) {
-                    *side += *right;
+                // the side channel takes the place of the channel restored from it, unless its
+                // extra bit only fits in the wider type, which needs a buffer of its own
+                let mut wide: Option<&mut FrameBuffer<S::Wide>> = if header.sample_size + 1 > S::BITS {
+                    scratch.wide.reset();
+                    Some(&mut scratch.wide)
+                } else {
+                    None
+                };
+                // side-right codes the side channel first, taking the place of the left
+                let side_first = assignment == ChannelAssignment::SideRightStereo;
+                let (side_vec, other_vec) = match side_first {
+                    true => (left_vec, right_vec),
+                    false => (right_vec, left_vec)
+                };
+                let sizes = match side_first {
+                    true => [header.sample_size + 1, header.sample_size],
+                    false => [header.sample_size, header.sample_size + 1]
+                };
+                for (i, &sample_size) in sizes.iter().enumerate() {
+                    let subframe = Subframe::from_reader(reader, sample_size, header.block_size, options.inspect_residuals)?;
+                    let stats = match (i == 0) == side_first {
+                        true => match wide.as_deref_mut() {
+                            Some(wide) => {
+                                side_vec.extend(header.block_size);
+                                subframe.decode(reader, wide)?
+                            },
+                            None => subframe.decode(reader, side_vec)?
+                        },
+                        false => subframe.decode(reader, other_vec)?
+                    };
+                    subframes.push(stats);
                 }
-            },
-            ChannelAssignment::MidSideStereo => {
-                let (mid_vec, tail) = blocks.split_first_mut()
-                    .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
-                let side_vec = tail.first_mut()
-                    .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?;
-                let mid = Subframe::from_reader(reader, header.sample_size, header.block_size)?;
-                mid.decode(reader, mid_vec)?;
-                let side = Subframe::from_reader(reader, header.sample_size + 1, header.block_size)?;
-                side.decode(reader, side_vec)?;
-                // correlate
-                for (mid, side) in mid_vec.iter_mut().zip(side_vec) {
-                    let s = *side;
-                    let m = (*mid * 2) | (s & 1);
-                    *mid = (m + s) / 2;
-                    *side = (m - s) / 2;
+                // correlate, where `side_vec` holds the side channel unless it is wide
+                let side_at = |i: usize, side_vec: &[S]| match &wide {
+                    Some(wide) => wide[i].to_i64(),
+                    None => side_vec[i].to_i64()
+                };
+                let len = side_vec.len().min(other_vec.len()).min(wide.as_ref().map_or(side_vec.len(), |x| x.len()));
+                for i in 0..len {
+                    let side = side_at(i, side_vec);
+                    let other = other_vec[i].to_i64();
+                    side_vec[i] = match assignment {
+                        // the right channel from the left
+                        ChannelAssignment::LeftSideStereo => S::from_i64(other - side),
+                        // the left channel from the right
+                        ChannelAssignment::SideRightStereo => S::from_i64(side + other),
+                        // the left channel into the block of mid, the right into that of side
+                        _ => {
+                            let m = (other * 2) | (side & 1);
+                            other_vec[i] = S::from_i64((m + side) / 2);
+                            S::from_i64((m - side) / 2)
+                        }
+                    };
                 }
             }
         };
+        // every channel holds the samples of the whole block, or the subframes went out of step
+        let num_channels = header.channel_assignment.num_channels();
+        if blocks.iter().take(num_channels).any(|x| x.len() != header.block_size) {
+            return Err(Error::from_code(ErrorCode::FrameSampleCountMismatch))
+        }
         // zero-padding to byte alignment
         reader.align_to_byte();
         // verify crc
-        let actual_crc16 = reader.compute_crc16_end();
-        let expected_crc16 = reader.read_u16()?;
-        if actual_crc16 != expected_crc16 {
+        let crc16 = reader.compute_crc16_end();
+        let footer = if options.verify_crc {
+            Frame::read_footer(reader, crc16)?
+        } else {
+            FrameFooter { crc16: reader.read_u16()? }
+        };
+        let stats = FrameStats {
+            byte_offset,
+            byte_len: reader.byte_offset() - byte_offset,
+            num_samples: header.block_size * header.channel_assignment.num_channels(),
+            subframes
+        };
+        let frame = Frame { header, stats, footer, blocks };
+        Ok(Some(frame))
+    }
+
+    // how the channels are coded, e.g. as mid and side
+    pub fn channel_assignment(&self) -> ChannelAssignment {
+        self.header.channel_assignment
+    }
+
+    // the number of samples per channel from the beginning of the stream through this frame,
+    // which tells the progress against the total of STREAMINFO, even after seeking
+    pub fn end_sample_number(&self) -> u64 {
+        self.header.sample_number + self.header.block_size as u64
+    }
+
+    // feeds the samples to the hasher the way the MD5 signature of STREAMINFO is computed
+    pub fn update_md5(&self, hasher: &mut HasherMd5) {
+        hasher.hash(&self.to_pcm_bytes(self.header.sample_size));
+    }
+
+    // the samples interleaved and little-endian in the fewest whole bytes holding `bps` bits,
+    // sign-extended rather than shifted, e.g. 12 bits in 2 bytes and 20 bits in 3 bytes.
+    // `write_to_samples` shifts them up to the byte boundary instead, as WAV expects.
+    pub fn to_pcm_bytes(&self, bps: usize) -> Vec<u8> {
+        let bytes_per_sample = bps.div_ceil(8).min(8);
+        let blocks = &self.blocks[..self.header.channel_assignment.num_channels()];
+        let mut bytes: Vec<u8> = Vec::with_capacity(self.header.block_size * blocks.len() * bytes_per_sample);
+        for i in 0..self.header.block_size {
+            for block in blocks {
+                let sample = block[i].to_i64().to_le_bytes();
+                bytes.extend_from_slice(&sample[..bytes_per_sample]);
+            }
+        }
+        bytes
+    }
+
+    // interleaves the samples of every channel, e.g. left and right alternately for stereo
+    pub fn interleaved(&self) -> Vec<S> {
+        let blocks = &self.blocks[..self.header.channel_assignment.num_channels()];
+        assert!(blocks.iter().all(|x| x.len() == self.header.block_size));
+        let mut samples: Vec<S> = Vec::with_capacity(self.header.block_size * blocks.len());
+        for i in 0..self.header.block_size {
+            for block in blocks {
+                samples.push(block[i]);
+            }
+        }
+        samples
+    }
+
+    // writes the samples interleaved, shifting them up to the next byte boundary
+    // so that e.g. 20-bit samples take the upper 20 bits of 24 bits
+    pub fn write_to_samples<T: SampleSink>(&self, sink: &mut T) -> core::result::Result<(), T::Error> {
+        let shift = self.header.sample_size.div_ceil(8) * 8 - self.header.sample_size;
+        let blocks = &self.blocks[..self.header.channel_assignment.num_channels()];
+        for i in 0..self.header.block_size {
+            for block in blocks {
+                sink.write_sample((block[i].to_i64() << shift) as i32)?;
+            }
+        }
+        Ok(())
+    }
+
+    // converts the samples of each channel to `T`, scaled from the bit depth of the frame
+    // to the full range of `T`. fails unless `T` holds the bit depth without loss, in which
+    // case `convert_lossy` has to be chosen explicitly.
+    pub fn convert<T: FromSample>(&self) -> Result<Vec<Vec<T>>> {
+        if self.header.sample_size > T::BITS {
+            return Err(Error::from_code(ErrorCode::SampleTypeTooNarrow))
+        }
+        Ok(self.convert_lossy())
+    }
+
+    // same as `convert`, but drops the least significant bits of samples deeper than `T`
+    pub fn convert_lossy<T: FromSample>(&self) -> Vec<Vec<T>> {
+        let bits = self.header.sample_size;
+        self.blocks[..self.header.channel_assignment.num_channels()].iter()
+            .map(|block| block.iter().map(|x| T::from_sample(x.to_i64(), bits)).collect())
+            .collect()
+    }
+
+    // converts the samples to the range of [-1.0, 1.0] by the bit depth of the frame,
+    // interleaving the channels
+    pub fn to_f32_interleaved(&self) -> Vec<f32> {
+        let scale = self.f32_scale();
+        let blocks = &self.blocks[..self.header.channel_assignment.num_channels()];
+        let mut samples: Vec<f32> = Vec::with_capacity(self.header.block_size * blocks.len());
+        for i in 0..self.header.block_size {
+            for block in blocks {
+                samples.push(block[i].to_i64() as f32 * scale);
+            }
+        }
+        samples
+    }
+
+    // same as `to_f32_interleaved`, but keeping each channel apart
+    pub fn to_f32_channels(&self) -> Vec<Vec<f32>> {
+        let scale = self.f32_scale();
+        self.blocks[..self.header.channel_assignment.num_channels()].iter()
+            .map(|block| block.iter().map(|x| x.to_i64() as f32 * scale).collect())
+            .collect()
+    }
+
+    fn f32_scale(&self) -> f32 {
+        1.0 / ((1u64 << (self.header.sample_size - 1)) as f32)
+    }
+}
+
+impl<'a> Frame<'a> {
+    // reads the CRC-16 footer of a frame and validates it against the one computed
+    // over the frame. the reader must be aligned to byte.
+    pub fn read_footer(reader: &mut dyn Decode, expected: u16) -> Result<FrameFooter> {
+        let crc16 = reader.read_u16()?;
+        if crc16 != expected {
             return Err(Error::from_code(ErrorCode::FrameCrcMismatch))
         }
-        let frame = Frame { header: header, blocks: blocks };
-        Ok(Some(frame))
+        Ok(FrameFooter { crc16 })
     }
 }
 
-#[derive(Debug)]
+// the last 2 bytes of a frame, at `byte_offset + byte_len - 2` of its stats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameFooter {
+    // CRC-16 of everything in the frame before the footer
+    pub crc16: u16
+}
+
+#[derive(Debug, Clone)]
 pub struct FrameHeader {
     pub sample_size: usize,
+    // the number of samples per channel, which every block of the decoded frame holds
     pub block_size: usize,
+    pub blocking_strategy: BlockingStrategy,
+    // the frame number for fixed blocking, or the number of the first sample for variable blocking
+    pub coded_number: u64,
+    // the number of the first sample in the frame, counted from the beginning of the stream
+    pub sample_number: u64,
+    // sample rate in Hz, that of STREAMINFO unless coded in the header. the coded one
+    // may differ from STREAMINFO for streams changing the rate midway unless decoding strictly
+    pub sample_rate: usize,
+    // set if the reserved bits are not 0, which is tolerated unless decoding strictly
+    pub reserved_bits_set: bool,
     pub channel_assignment: ChannelAssignment
 }
 
 impl FrameHeader {
-    pub fn from_reader(reader: &mut Decode, stream_info: &StreamInfo) -> Result<Option<Self>> {
-        reader.compute_crc8_begin();
+    pub fn from_reader(reader: &mut dyn Decode, stream_info: &StreamInfo, options: &DecodeOptions) -> Result<Option<Self>> {
+        if options.verify_crc {
+            reader.compute_crc8_begin();
+        }
         let sync_code = reader.read_u16_bits(14);
         match sync_code {
             Ok(sync_code) => {
@@ -112,50 +583,76 @@ impl FrameHeader {
             }
         };
         // parameters
-        let _zero              = reader.read_bool()?;
-        let _blocking_strategy = reader.read_u8_bits(1)?;
+        let zero               = reader.read_bool()?;
+        let blocking_strategy  = reader.read_bool()?;
         let block_size_bits    = reader.read_u8_bits(4)?;
         let sample_rate_bits   = reader.read_u8_bits(4)?;
         let channel_bits       = reader.read_u8_bits(4)?;
         let sample_size_bits   = reader.read_u8_bits(3)?;
-        let _reserved          = reader.read_bool()?;
-        // skip utf-8 coded
-        let mut v1: u32 = reader.read_u8()? as u32;
-        while v1 >= 0b1100_0000 {
-            reader.read_u8()?;
-            v1 = (v1 << 1) & 0xff;
+        let reserved           = reader.read_bool()?;
+        // both must be 0, usually meaning we have lost sync otherwise
+        let reserved_bits_set = zero || reserved;
+        if reserved_bits_set && options.strict {
+            return Err(Error::from_code(ErrorCode::FrameOutOfSync))
         }
+        let coded_number = read_utf8_coded(reader)?;
         // variable block size
         let variable_block_size: Option<usize> = match block_size_bits {
             0b0110 => reader.read_u8()
                 .map(|x| (x as usize) + 1)
-                .map(|x| Some(x))?,
+                .map(Some)?,
             0b0111 => reader.read_u16()
                 .map(|x| (x as usize) + 1)
-                .map(|x| Some(x))?,
+                .map(Some)?,
             _ => None
         };
-        // variable sample rate
-        let _ = match sample_rate_bits {
-            0b1100 => reader.read_u8()? as u16,
-            0b1101 => reader.read_u16()?,
-            0b1110 => reader.read_u16()?,
-            _ => 0u16
+        let coded_sample_rate: Option<usize> = match sample_rate_bits {
+            // get from STREAMINFO
+            0b0000 => None,
+            0b0001 => Some(88200),
+            0b0010 => Some(176400),
+            0b0011 => Some(192000),
+            0b0100 => Some(8000),
+            0b0101 => Some(16000),
+            0b0110 => Some(22050),
+            0b0111 => Some(24000),
+            0b1000 => Some(32000),
+            0b1001 => Some(44100),
+            0b1010 => Some(48000),
+            0b1011 => Some(96000),
+            // in kHz
+            0b1100 => Some((reader.read_u8()? as usize) * 1000),
+            // in Hz
+            0b1101 => Some(reader.read_u16()? as usize),
+            // in tens of Hz
+            0b1110 => Some((reader.read_u16()? as usize) * 10),
+            // invalid, usually meaning we have lost sync, which falls back to STREAMINFO as well
+            _ => {
+                if options.strict {
+                    return Err(Error::from_code(ErrorCode::FrameOutOfSync))
+                }
+                None
+            }
         };
         // crc validate
         let actual_crc8 = reader.compute_crc8_end();
         let expected_crc8 = reader.read_u8()?;
-        if actual_crc8 != expected_crc8 {
+        if options.verify_crc && actual_crc8 != expected_crc8 {
             return Err(Error::from_code(ErrorCode::FrameHeaderCrcMismatch))
         }
+        if options.strict && coded_sample_rate.is_some_and(|x| x != stream_info.sample_rate) {
+            return Err(Error::from_code(ErrorCode::FrameSampleRateMismatch))
+        }
+        let sample_rate = coded_sample_rate.unwrap_or(stream_info.sample_rate);
         let sample_size = |n: u8| -> Option<usize> {
             let size = match n {
-                0b000 => stream_info.bits_per_sample as usize,
+                0b000 => stream_info.bits_per_sample,
                 0b001 => 8,
                 0b010 => 12,
                 0b100 => 16,
                 0b101 => 20,
                 0b110 => 24,
+                0b111 => 32,
                 _ => return None
             };
             Some(size)
@@ -171,113 +668,229 @@ impl FrameHeader {
             };
             Some(size)
         };
+        let blocking_strategy = if blocking_strategy {
+            BlockingStrategy::Variable
+        } else {
+            BlockingStrategy::Fixed
+        };
+        // every frame but the last of a fixed-blocksize stream has the largest block size
+        let sample_number = match blocking_strategy {
+            BlockingStrategy::Fixed => coded_number * stream_info.max_block_size as u64,
+            BlockingStrategy::Variable => coded_number
+        };
         let header = FrameHeader {
             sample_size: sample_size(sample_size_bits)
                 .ok_or_else(|| Error::from_code(ErrorCode::FrameSampleSizeUnknown))?,
             block_size: block_size(block_size_bits)
                 .ok_or_else(|| Error::from_code(ErrorCode::FrameBlockSizeUnknown))?,
+            blocking_strategy,
+            coded_number,
+            sample_number,
+            sample_rate,
+            reserved_bits_set,
             channel_assignment: ChannelAssignment::parse(channel_bits)
                 .ok_or_else(|| Error::from_code(ErrorCode::FrameChannelAssignmentUnknown))?
         };
+        // the channels cannot change within a stream, usually meaning corruption otherwise
+        if header.channel_assignment.num_channels() != stream_info.number_of_channels {
+            return Err(Error::from_code(ErrorCode::FrameChannelCountMismatch))
+        }
+        // the buffers are allocated up to the largest block size
+        if header.block_size > stream_info.max_block_size {
+            return Err(Error::from_code(ErrorCode::FrameBlockSizeExceedsMax))
+        }
         Ok(Some(header))
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockingStrategy {
+    Fixed,
+    Variable
+}
+
+// decodes the number coded the same way as UTF-8 extended to 36 bits
+fn read_utf8_coded(reader: &mut dyn Decode) -> Result<u64> {
+    let first = reader.read_u8()?;
+    // the number of leading ones tells the length of the sequence
+    let length = first.leading_ones() as usize;
+    let mut value: u64 = match length {
+        0 => return Ok(first as u64),
+        2..=7 => (first & (0x7f >> length)) as u64,
+        _ => return Err(Error::from_code(ErrorCode::FrameNumberInvalid))
+    };
+    for _ in 1..length {
+        let byte = reader.read_u8()?;
+        if byte & 0b1100_0000 != 0b1000_0000 {
+            return Err(Error::from_code(ErrorCode::FrameNumberInvalid))
+        }
+        value = (value << 6) | ((byte & 0b0011_1111) as u64);
+    }
+    Ok(value)
+}
+
+// the order of the linear predictor coded in 5 bits
+const MAX_LPC_ORDER: usize = 32;
+
+// the linear predictor of a subframe, applied once its residuals are read
+#[derive(Debug)]
+struct Predictor {
+    // the first `order` of them, held inline not to allocate for every subframe
+    coefficients: [i32; MAX_LPC_ORDER],
+    shift: i32,
+    order: usize
+}
+
+// what is left of decoding a subframe after reading it
+#[derive(Debug)]
+struct SubframeRemainder {
+    constant: Option<i64>,
+    predictor: Option<Predictor>,
+    residual: Option<ResidualStats>
+}
+
 // SUBFRAME
 #[derive(Debug)]
 struct Subframe {
     method: PredictionMethod,
     sample_size: usize,
-    block_size: usize
+    block_size: usize,
+    wasted_bits_per_sample: usize,
+    // collects `ResidualStats`
+    inspect: bool
 }
 
 impl Subframe {
-    fn from_reader(reader: &mut Decode, sample_size: usize, block_size: usize) -> Result<Self> {
+    fn from_reader(reader: &mut dyn Decode, sample_size: usize, block_size: usize, inspect: bool) -> Result<Self> {
         let header = SubframeHeader::from_reader(reader)?;
+        // every bit may be wasted, leaving samples of zero bits which are all zeros
+        if header.wasted_bits_per_sample > sample_size {
+            return Err(Error::from_code(ErrorCode::SubframeWastedBitsInvalid))
+        }
         let sample_size = sample_size - header.wasted_bits_per_sample;
+        // validate the predictor before reading any warm-up samples of it
+        match header.method {
+            PredictionMethod::Fixed(order) if order > 4 => {
+                return Err(Error::from_code(ErrorCode::FixedLPCCoefficientUnknown))
+            },
+            PredictionMethod::Fixed(order) | PredictionMethod::Fir(order) if order > block_size => {
+                return Err(Error::from_code(ErrorCode::SubframeOrderExceedsBlockSize))
+            },
+            _ => ()
+        }
         let subframe = Subframe { 
             method: header.method,
-            sample_size: sample_size,
-            block_size: block_size
+            sample_size,
+            block_size,
+            wasted_bits_per_sample: header.wasted_bits_per_sample,
+            inspect
         };
         Ok(subframe)
     }
 
-    fn decode(&self, reader: &mut Decode, vec: &mut Vec<i32>) -> Result<()> {
-        match self.method {
-            PredictionMethod::Constant => self.decode_constant(reader, vec),
-            PredictionMethod::Verbatim => self.decode_verbatim(reader, vec),
-            PredictionMethod::Fixed(order) => self.decode_fixed(reader, vec, order),
-            PredictionMethod::Fir(order) => self.decode_fir(reader, vec, order)
+    fn decode<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>) -> Result<SubframeStats> {
+        let remainder = self.read(reader, vec)?;
+        self.restore(remainder, vec)
+    }
+
+    // reads the subframe into the buffer, leaving the prediction to `restore`
+    fn read<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>) -> Result<SubframeRemainder> {
+        let remainder = match self.method {
+            PredictionMethod::Constant => SubframeRemainder {
+                constant: Some(self.decode_constant(reader, vec)?),
+                predictor: None,
+                residual: None
+            },
+            PredictionMethod::Verbatim => {
+                self.decode_verbatim(reader, vec)?;
+                SubframeRemainder { constant: None, predictor: None, residual: None }
+            },
+            PredictionMethod::Fixed(order) => {
+                let (predictor, residual) = self.decode_fixed(reader, vec, order)?;
+                SubframeRemainder { constant: None, predictor: Some(predictor), residual }
+            },
+            PredictionMethod::Fir(order) => {
+                let (predictor, residual) = self.decode_fir(reader, vec, order)?;
+                SubframeRemainder { constant: None, predictor: Some(predictor), residual }
+            }
+        };
+        Ok(remainder)
+    }
+
+    // restores the signal from the warm-up samples and residuals read, touching nothing but the samples
+    fn restore<S: Sample>(&self, remainder: SubframeRemainder, vec: &mut [S]) -> Result<SubframeStats> {
+        let wasted = self.wasted_bits_per_sample;
+        if let Some(predictor) = remainder.predictor {
+            let coefficients = &predictor.coefficients[..predictor.order];
+            self.restore_signals(coefficients, predictor.shift, predictor.order, vec)?;
+        }
+        // restore the zeros dropped from the least significant bits
+        if wasted > 0 {
+            for sample in vec.iter_mut() {
+                *sample = S::from_i64(sample.to_i64() << wasted);
+            }
+        }
+        Ok(self.stats(remainder.constant, remainder.residual))
+    }
+
+    // discards the subframe read, leaving the samples silent without restoring the signal
+    fn skip<S: Sample>(&self, remainder: SubframeRemainder, vec: &mut [S]) -> SubframeStats {
+        for sample in vec.iter_mut() {
+            *sample = S::default();
         }
+        self.stats(remainder.constant, remainder.residual)
+    }
+
+    fn stats(&self, constant: Option<i64>, residual: Option<ResidualStats>) -> SubframeStats {
+        let constant = constant.map(|x| x << self.wasted_bits_per_sample);
+        let warm_up = match self.method {
+            PredictionMethod::Fixed(order) | PredictionMethod::Fir(order) => order,
+            _ => 0
+        };
+        SubframeStats { method: self.method, constant, warm_up, wasted_bits: self.wasted_bits_per_sample, residual }
     }
 
-    fn decode_constant(&self, reader: &mut Decode, vec: &mut Vec<i32>) -> Result<()> {
+    fn decode_constant<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>) -> Result<i64> {
         let bps = self.sample_size;
         let num_samples = self.block_size;
-        let sample = sign_extend(reader.read_u64_bits(bps)?, bps) as i32;
-        let offset = vec.len();
-        vec.resize(offset + num_samples, sample);
-        Ok(())
+        let value = reader.read_i64_bits(bps)?;
+        vec.extend_with(num_samples, || Ok(S::from_i64(value)))?;
+        Ok(value)
     }
 
-    fn decode_verbatim(&self, reader: &mut Decode, vec: &mut Vec<i32>) -> Result<()> {
+    fn decode_verbatim<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>) -> Result<()> {
         let bps = self.sample_size;
         let num_samples = self.block_size;
-        let offset = vec.len();
-        vec.resize(offset + num_samples, 0);
-        let slice = &mut vec[offset..];
-        for sample in slice {
-            *sample = sign_extend(reader.read_u64_bits(bps)?, bps) as i32;
-        }
-        Ok(())
+        vec.extend_with(num_samples, || Ok(S::from_i64(reader.read_i64_bits(bps)?)))
     }
 
     // SHORTEN: SIMPLE LOSSLESS AND NEAR-LOSSLESS WAVEFORM COMPRESSION
     // 3.2 Linear Prediction
     // @see http://svr-www.eng.cam.ac.uk/reports/abstracts/robinson_tr156.html
-    fn decode_fixed(&self, reader: &mut Decode, vec: &mut Vec<i32>, order: usize) -> Result<()> {
+    fn decode_fixed<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>, order: usize) -> Result<(Predictor, Option<ResidualStats>)> {
         let bps = self.sample_size;
         // unencoded warm-up samples
-        {
-            let offset = vec.len();
-            vec.resize(offset + order, 0);
-            let slice = &mut vec[offset..];
-            for sample in slice {
-                *sample = sign_extend(reader.read_u64_bits(bps)?, bps) as i32;
-            }
-        }
+        vec.extend_with(order, || Ok(S::from_i64(reader.read_i64_bits(bps)?)))?;
         // subframe residuals
-        self.decode_residuals(reader, vec, order)?;
+        let residual = self.decode_residuals(reader, vec, order)?;
         // LPC
-        let obtain_coefficients = |order: usize| -> Option<Vec<i32>> {
-            let v = match order {
-                0 => vec![],
-                1 => vec![1],
-                2 => vec![2, -1],
-                3 => vec![3, -3, 1],
-                4 => vec![4, -6, 4, -1],
-                _ => return None
-            };
-            Some(v)
+        let fixed: &[i32] = match order {
+            0 => &[],
+            1 => &[1],
+            2 => &[2, -1],
+            3 => &[3, -3, 1],
+            4 => &[4, -6, 4, -1],
+            _ => return Err(Error::from_code(ErrorCode::FixedLPCCoefficientUnknown))
         };
-        let coefficients = obtain_coefficients(order)
-            .ok_or_else(|| Error::from_code(ErrorCode::FixedLPCCoefficientUnknown))?;
-        self.restore_signals(coefficients, 0, order, vec)?;
-        Ok(())
+        let mut coefficients = [0i32; MAX_LPC_ORDER];
+        coefficients[..order].copy_from_slice(fixed);
+        Ok((Predictor { coefficients, shift: 0, order }, residual))
     }
 
-    fn decode_fir(&self, reader: &mut Decode, vec: &mut Vec<i32>, order: usize) -> Result<()> {
+    fn decode_fir<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>, order: usize) -> Result<(Predictor, Option<ResidualStats>)> {
         let bps = self.sample_size;
         // unencoded warm-up samples
-        {
-            let offset = vec.len();
-            vec.resize(offset + order, 0);
-            let slice = &mut vec[offset..];
-            for sample in slice {
-                *sample = sign_extend(reader.read_u64_bits(bps)?, bps) as i32;
-            }
-        }
+        vec.extend_with(order, || Ok(S::from_i64(reader.read_i64_bits(bps)?)))?;
         // quantized linear predictor coefficients' precision in bits
         let precision_bits = reader.read_u8_bits(4)?;
         if precision_bits == 0b1111 {
@@ -285,35 +898,51 @@ impl Subframe {
         }
         let precision = (precision_bits as usize) + 1;
         // quantized linear predictor coefficient shift needed in bits
-        let shift = sign_extend(reader.read_u64_bits(5)?, 5) as i32;
+        let shift = reader.read_i32_bits(5)?;
         // unencoded predictor coefficients
-        let mut coefficients: Vec<i32> = Vec::new();
-        coefficients.resize(order, 0);
-        for coefficient in &mut coefficients[..] {
-            *coefficient = sign_extend(reader.read_u64_bits(precision)?, precision) as i32;
+        let mut coefficients = [0i32; MAX_LPC_ORDER];
+        let slots = coefficients.get_mut(..order)
+            .ok_or_else(|| Error::from_code(ErrorCode::LPCSignalRestoreFailure))?;
+        for coefficient in slots {
+            *coefficient = reader.read_i32_bits(precision)?;
         }
         // subframe residuals
-        self.decode_residuals(reader, vec, order)?;
-        // LPC
-        self.restore_signals(coefficients, shift, order, vec)?;
-        Ok(())
+        let residual = self.decode_residuals(reader, vec, order)?;
+        Ok((Predictor { coefficients, shift, order }, residual))
     }
 
-    fn restore_signals(&self, coefficients: Vec<i32>, shift: i32, order: usize, vec: &mut Vec<i32>) -> Result<()> {
-        if coefficients.len() != order || vec.len() != self.block_size || shift < 0 {
+    fn restore_signals<S: Sample>(&self, coefficients: &[i32], shift: i32, order: usize, vec: &mut [S]) -> Result<()> {
+        if coefficients.len() != order || order > self.block_size || vec.len() != self.block_size || shift < 0 {
             return Err(Error::from_code(ErrorCode::LPCSignalRestoreFailure))
         }
+        // the range of the sample size, which only a corrupt stream exceeds
+        let (min, max) = match self.sample_size {
+            0 => (0, 0),
+            n => (-(1i64 << (n - 1)), (1i64 << (n - 1)) - 1)
+        };
         for i in order..self.block_size {
             let mut sample: i64 = 0;
             for (j, coeff) in coefficients.iter().enumerate() {
-                sample += (*coeff as i64) * (vec[i-j-1] as i64);
+                sample += (*coeff as i64) * vec[i-j-1].to_i64();
             }
-            vec[i] += (sample >> shift) as i32
+            let restored = vec[i].to_i64() + (sample >> shift);
+            // residuals fit in 32 bits, so a type as wide holds them as they are and the sum
+            // is checked before it could wrap. in a narrower type they have wrapped already,
+            // which the sum wraps back the same way, thus checked after wrapping.
+            let restored = match S::BITS >= 32 {
+                true => restored,
+                false => S::from_i64(restored).to_i64()
+            };
+            if !(min..=max).contains(&restored) {
+                return Err(Error::from_code(ErrorCode::LPCSignalRestoreFailure))
+            }
+            vec[i] = S::from_i64(restored);
         }
         Ok(())
     }
 
-    fn decode_residuals(&self, reader: &mut Decode, vec: &mut Vec<i32>, predictor_order: usize) -> Result<()> {
+    // returns how the residuals are coded if inspecting
+    fn decode_residuals<S: Sample>(&self, reader: &mut dyn Decode, vec: &mut FrameBuffer<S>, predictor_order: usize) -> Result<Option<ResidualStats>> {
         let coding_method = reader.read_u8_bits(2)?;
         // rice parameter bit depth varies by coding method
         let depth = match coding_method {
@@ -322,6 +951,12 @@ impl Subframe {
             _ => return Err(Error::from_code(ErrorCode::ResidualCodingMethodUnknown))
         };
         let partition_order = reader.read_u8_bits(4)?;
+        // the block size must be divisible into the partitions, the first of which
+        // must be large enough for the warm-up samples
+        let partition_size = self.block_size >> partition_order;
+        if partition_size << partition_order != self.block_size || partition_size < predictor_order {
+            return Err(Error::from_code(ErrorCode::ResidualPartitionMismatch))
+        }
         // determine the number of samples in the partition
         let determine_num_samples = |first: bool| -> usize {
             if partition_order == 0 {
@@ -331,30 +966,48 @@ impl Subframe {
             if !first {
                 return self.block_size >> partition_order;
             }
-            return (self.block_size >> partition_order) - predictor_order;
+            (self.block_size >> partition_order) - predictor_order
         };
         // there will be 2^order partitions.
         let num_partitions = 1 << (partition_order as i32);
         let escape = (1u8 << depth) - 1;
+        let mut partitions: Vec<ResidualPartition> = Vec::new();
         for i_partition in 0..num_partitions {
             let num_samples = determine_num_samples(i_partition == 0);
-            let parameter = reader.read_u8_bits(depth)? as usize;
-            assert!(parameter != (escape as usize));
-            // decode
-            let offset = vec.len();
-            vec.resize(offset + num_samples, 0);
-            let slice = &mut vec[offset..];
-            for sample in slice {
-                *sample = reader.decode_rice(parameter)?;
+            let parameter = reader.read_u8_bits(depth)?;
+            if parameter == escape {
+                // unencoded residuals in the given number of bits
+                let bits = reader.read_u8_bits(5)?;
+                if self.inspect {
+                    partitions.push(ResidualPartition::Escaped(bits));
+                }
+                let bits = bits as usize;
+                vec.extend_with(num_samples, || Ok(S::from_i64(reader.read_i64_bits(bits)?)))?;
+                continue;
+            }
+            if self.inspect {
+                partitions.push(ResidualPartition::Rice(parameter));
+            }
+            // decode in chunks through a buffer of the type the reader decodes into
+            let mut residuals = [0i32; 256];
+            let mut remaining = num_samples;
+            while remaining > 0 {
+                let n = remaining.min(residuals.len());
+                reader.decode_rice_block(parameter as usize, &mut residuals[..n])?;
+                vec.append(residuals[..n].iter().map(|&x| S::from_i64(x as i64)));
+                remaining -= n;
             }
         }
-        Ok(())
+        if !self.inspect {
+            return Ok(None)
+        }
+        Ok(Some(ResidualStats { parameter_bits: depth, partition_order: partition_order as usize, partitions }))
     }
 }
 
 // SUBFRAME_HEADER
-#[derive(Debug, Clone, Copy)]
-enum PredictionMethod {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PredictionMethod {
     Constant,
     Verbatim,
     Fixed(usize),
@@ -381,7 +1034,7 @@ struct SubframeHeader {
 }
 
 impl SubframeHeader {
-    pub fn from_reader(reader: &mut Decode) -> Result<Self> {
+    pub fn from_reader(reader: &mut dyn Decode) -> Result<Self> {
         // Zero bit padding, to prevent sync-fooling string of 1s
         let zero = reader.read_bool()?;
         if zero {
@@ -402,14 +1055,14 @@ impl SubframeHeader {
             }
         }
         let header = SubframeHeader {
-            method: method,
-            wasted_bits_per_sample: wasted_bits_per_sample
+            method,
+            wasted_bits_per_sample
         };
         Ok(header)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ChannelAssignment {
     Independent(usize),
     LeftSideStereo,
@@ -428,22 +1081,725 @@ impl ChannelAssignment {
         };
         Some(assignment)
     }
-}
 
-fn sign_extend(x: u64, n: usize) -> i64 {
-    let m = 64 - n;
-    ((x << m) as i64) >> m
+    pub fn num_channels(&self) -> usize {
+        match *self {
+            ChannelAssignment::Independent(num_channels) => num_channels,
+            _ => 2
+        }
+    }
+
+    // whether the stereo channels are coded with a side channel rather than as is
+    pub fn is_decorrelated(&self) -> bool {
+        !matches!(*self, ChannelAssignment::Independent(_))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
+    use super::super::bits::{BitRead, BitReader};
+    use super::super::decode::{DecodingRead, DecodingReadProxy};
+    use super::super::stream::Stream;
+
+    #[test]
+    fn test_convert() {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo24.flac");
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut frames: usize = 0;
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            // 24 bits do not fit in 16 bits without dropping some
+            match frame.convert::<i16>().unwrap_err().code() {
+                ErrorCode::SampleTypeTooNarrow => (),
+                code => panic!("unexpected error {:?}", code)
+            }
+            let lossy = frame.convert_lossy::<i16>();
+            let wide = frame.convert::<i32>().unwrap();
+            for (i, block) in frame.blocks.iter().enumerate() {
+                let expected: Vec<i16> = block.iter().map(|&x| (x >> 8) as i16).collect();
+                assert_eq!(lossy[i], expected);
+                let expected: Vec<i32> = block.iter().map(|&x| x << 8).collect();
+                assert_eq!(wide[i], expected);
+            }
+            assert_eq!(frame.convert::<f32>().unwrap(), frame.to_f32_channels());
+            frames += 1;
+        }).unwrap();
+        assert!(frames > 0);
+    }
+
+    #[test]
+    fn test_sample_counts() {
+        // every channel assignment, restored or skipped
+        for options in [DecodeOptions::default(), DecodeOptions::default().with_channels(&[])].iter() {
+            let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::with_options(&mut reader, *options).unwrap();
+            let mut counts: Vec<usize> = Vec::new();
+            stream.decode_frames(&mut reader, |frame: &Frame| {
+                assert!(frame.blocks.iter().all(|x| x.len() == frame.header.block_size));
+                counts.push(frame.header.block_size);
+            }).unwrap();
+            assert_eq!(counts, vec![64, 64, 64, 64, 40]);
+        }
+    }
+
+    #[test]
+    fn test_channel_assignment() {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut modes: Vec<ChannelAssignment> = Vec::new();
+        let mut ends: Vec<u64> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            modes.push(frame.channel_assignment());
+            ends.push(frame.end_sample_number());
+        }).unwrap();
+        assert_eq!(ends, vec![64, 128, 192, 256, 296]);
+        assert_eq!(modes, vec![
+            ChannelAssignment::Independent(2),
+            ChannelAssignment::LeftSideStereo,
+            ChannelAssignment::MidSideStereo,
+            ChannelAssignment::SideRightStereo,
+            ChannelAssignment::Independent(2)
+        ]);
+        assert!(!ChannelAssignment::Independent(2).is_decorrelated());
+        assert!(ChannelAssignment::LeftSideStereo.is_decorrelated());
+        assert!(ChannelAssignment::SideRightStereo.is_decorrelated());
+        assert!(ChannelAssignment::MidSideStereo.is_decorrelated());
+    }
+
+    #[test]
+    fn test_read_footer() {
+        let mut bytes: &[u8] = &[0x15, 0xd1, 0x15, 0xd1];
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        assert_eq!(Frame::read_footer(&mut reader, 0x15d1).unwrap(), FrameFooter { crc16: 0x15d1 });
+        match Frame::read_footer(&mut reader, 0x15d0).unwrap_err().code() {
+            ErrorCode::FrameCrcMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    fn read_header_with_options(bytes: &[u8], options: &DecodeOptions) -> Result<FrameHeader> {
+        let mut bytes: &[u8] = bytes;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut stream_proxy = DecodingReadProxy::new(&mut bytes);
+        let mut stream_reader = BitReader::new(&mut stream_proxy);
+        let stream = Stream::new(&mut stream_reader).unwrap();
+        FrameHeader::from_reader(&mut reader, &stream.stream_info, options)
+            .map(|x| x.unwrap())
+    }
+
+    fn read_header(bytes: &[u8]) -> FrameHeader {
+        read_header_with_options(bytes, &DecodeOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_to_pcm_bytes() {
+        let mut header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+        header.block_size = 2;
+        let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(), FrameBuffer::new()];
+        for &(left, right) in &[(-2048, 2047), (0x7_ffff, -1)] {
+            blocks[0].push(left);
+            blocks[1].push(right);
+        }
+        let stats = FrameStats { byte_offset: 0, byte_len: 0, num_samples: 4, subframes: Vec::new() };
+        let frame = Frame { header, stats, footer: FrameFooter { crc16: 0 }, blocks: &mut blocks };
+        // 12 bits in 2 bytes
+        assert_eq!(frame.to_pcm_bytes(12)[..4], [0x00, 0xf8, 0xff, 0x07]);
+        // 20 bits in 3 bytes
+        assert_eq!(frame.to_pcm_bytes(20), vec![
+            0x00, 0xf8, 0xff, 0xff, 0x07, 0x00,
+            0xff, 0xff, 0x07, 0xff, 0xff, 0xff
+        ]);
+        assert_eq!(frame.to_pcm_bytes(8).len(), 4);
+    }
+
+    #[test]
+    fn test_pcm_bytes_md5() {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo24.flac");
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut hasher = HasherMd5::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            hasher.hash(&frame.to_pcm_bytes(stream.stream_info.bits_per_sample));
+        }).unwrap();
+        assert_eq!(hasher.state(), stream.stream_info.signature);
+    }
+
+    #[test]
+    fn test_to_f32() {
+        for &sample_size in &[8, 12, 16, 20, 24, 32] {
+            let full_scale = 1i64 << (sample_size - 1);
+            let mut header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+            header.sample_size = sample_size;
+            header.block_size = 3;
+            let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(), FrameBuffer::new()];
+            for &(left, right) in &[(-full_scale, 0), (full_scale / 2, -full_scale / 4), (full_scale - 1, 1)] {
+                blocks[0].push(left as i32);
+                blocks[1].push(right as i32);
+            }
+            let stats = FrameStats { byte_offset: 0, byte_len: 0, num_samples: 6, subframes: Vec::new() };
+            let frame = Frame { header, stats, footer: FrameFooter { crc16: 0 }, blocks: &mut blocks };
+            let samples = frame.to_f32_interleaved();
+            assert_eq!(&samples[..4], &[-1.0, 0.0, 0.5, -0.25]);
+            assert!(samples[4] <= 1.0 && samples[4] > 0.99);
+            assert_eq!(samples[5], 1.0 / full_scale as f32);
+            let channels = frame.to_f32_channels();
+            assert_eq!(channels, vec![
+                vec![samples[0], samples[2], samples[4]],
+                vec![samples[1], samples[3], samples[5]]
+            ]);
+        }
+    }
+
+    #[test]
+    fn test_extend_without_zero_fill() {
+        // the same as resizing with zeros then filling, whether the stale samples cover the
+        // extension, a part of it or none of it
+        let values: Vec<i32> = (1..=12).collect();
+        for &num_stale in &[0, 3, 5, 8, 12, 20] {
+            for &(offset, n) in &[(0, 5), (2, 10), (5, 7)] {
+                let mut expected: Vec<i32> = values[..offset].to_vec();
+                expected.resize(offset + n, 0);
+                expected[offset..].copy_from_slice(&values[offset..offset + n]);
+                let stale = || {
+                    let mut buffer = FrameBuffer { samples: vec![-7; num_stale], len: 0 };
+                    buffer.append(values[..offset].iter().copied());
+                    buffer
+                };
+                let mut buffer = stale();
+                let mut iter = values[offset..offset + n].iter();
+                buffer.extend_with(n, || Ok(*iter.next().unwrap())).unwrap();
+                assert_eq!(&buffer[..], &expected[..]);
+                let mut buffer = stale();
+                buffer.append(values[offset..offset + n].iter().copied());
+                assert_eq!(&buffer[..], &expected[..]);
+            }
+        }
+        // the cursor stays on an error
+        let mut buffer: FrameBuffer = FrameBuffer { samples: vec![-7; 4], len: 0 };
+        let mut count = 0;
+        let result = buffer.extend_with(6, || {
+            count += 1;
+            match count {
+                6 => Err(Error::from_code(ErrorCode::SubframeOutOfSync)),
+                _ => Ok(count)
+            }
+        });
+        assert!(result.is_err());
+        assert_eq!(buffer.len(), 0);
+    }
+
+    #[test]
+    fn test_stale_buffers() {
+        // the samples left from the previous frames are all overwritten, as if the buffers were zeroed
+        let files: &[&[u8]] = &[
+            include_bytes!("../testdata/stereo16.flac"),
+            include_bytes!("../testdata/stereo24.flac"),
+            include_bytes!("../testdata/stereo32.flac")
+        ];
+        for &data in files {
+            let mut bytes: &[u8] = data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            let info = stream.stream_info;
+            let mut stale: Vec<FrameBuffer> = (0..info.number_of_channels)
+                .map(|_| FrameBuffer { samples: vec![0x5a5a_5a5a; info.max_block_size], len: 0 })
+                .collect();
+            loop {
+                let offset = reader.byte_offset() as usize;
+                let actual: Vec<Vec<i32>> = match Frame::from_reader(&mut reader, &info, &stream.options, &mut stale).unwrap() {
+                    None => break,
+                    Some(frame) => frame.blocks.iter().map(|x| x.to_vec()).collect()
+                };
+                // the same frame again into fresh buffers
+                let mut bytes: &[u8] = &data[offset..];
+                let mut proxy = DecodingReadProxy::new(&mut bytes);
+                let mut fresh_reader = BitReader::new(&mut proxy);
+                let mut fresh: Vec<FrameBuffer> = vec![FrameBuffer::new(); info.number_of_channels];
+                let frame = Frame::from_reader(&mut fresh_reader, &info, &stream.options, &mut fresh).unwrap().unwrap();
+                let expected: Vec<Vec<i32>> = frame.blocks.iter().map(|x| x.to_vec()).collect();
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stale_scratch() {
+        // the side channel of 16-bit stereo is decoded into the wide buffer kept across frames
+        let data: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut bytes: &[u8] = data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let info = stream.stream_info;
+        let mut blocks: Vec<FrameBuffer<i16>> = vec![FrameBuffer::new(); 2];
+        let mut scratch: FrameScratch<i16> = FrameScratch {
+            wide: FrameBuffer { samples: vec![0x5a5a_5a5a; info.max_block_size], len: 0 },
+            subframes: Vec::new()
+        };
+        let mut num_side_coded: usize = 0;
+        loop {
+            let offset = reader.byte_offset() as usize;
+            let actual: Vec<Vec<i16>> = match Frame::from_reader_with_scratch(&mut reader, &info, &stream.options, &mut blocks, &mut scratch).unwrap() {
+                None => break,
+                Some(frame) => {
+                    if frame.channel_assignment() != ChannelAssignment::Independent(2) {
+                        num_side_coded += 1;
+                    }
+                    frame.blocks.iter().map(|x| x.to_vec()).collect()
+                }
+            };
+            let mut bytes: &[u8] = &data[offset..];
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut fresh_reader = BitReader::new(&mut proxy);
+            let mut fresh: Vec<FrameBuffer<i16>> = vec![FrameBuffer::new(); 2];
+            let frame = Frame::from_reader(&mut fresh_reader, &info, &stream.options, &mut fresh).unwrap().unwrap();
+            let expected: Vec<Vec<i16>> = frame.blocks.iter().map(|x| x.to_vec()).collect();
+            assert_eq!(actual, expected);
+        }
+        assert!(num_side_coded > 0);
+        // never grown past the block size it was given
+        assert_eq!(scratch.wide.samples.len(), info.max_block_size);
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let mut header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+        header.block_size = 3;
+        let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(), FrameBuffer::new()];
+        for i in 1..=3 {
+            blocks[0].push(i);
+            blocks[1].push(-i);
+        }
+        let stats = FrameStats { byte_offset: 0, byte_len: 0, num_samples: 6, subframes: Vec::new() };
+        let frame = Frame { header, stats, footer: FrameFooter { crc16: 0 }, blocks: &mut blocks };
+        assert_eq!(frame.interleaved(), vec![1, -1, 2, -2, 3, -3]);
+    }
+
+    #[test]
+    fn test_write_to_samples() {
+        for &(sample_size, shift) in &[(8, 0), (12, 4), (16, 0), (20, 4), (24, 0), (32, 0)] {
+            let full_scale = 1i64 << (sample_size - 1);
+            let mut header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+            header.sample_size = sample_size;
+            header.block_size = 2;
+            let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(), FrameBuffer::new()];
+            for &(left, right) in &[(-full_scale, -1i64), (full_scale - 1, 1)] {
+                blocks[0].push(left as i32);
+                blocks[1].push(right as i32);
+            }
+            let stats = FrameStats { byte_offset: 0, byte_len: 0, num_samples: 4, subframes: Vec::new() };
+            let frame = Frame { header, stats, footer: FrameFooter { crc16: 0 }, blocks: &mut blocks };
+            let mut samples: Vec<i32> = Vec::new();
+            frame.write_to_samples(&mut samples).unwrap();
+            let expected: Vec<i32> = [-full_scale, -1, full_scale - 1, 1].iter()
+                .map(|&x| (x << shift) as i32)
+                .collect();
+            assert_eq!(samples, expected);
+        }
+    }
+
+    #[test]
+    fn test_coded_number() {
+        let header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+        assert_eq!((header.blocking_strategy, header.coded_number), (BlockingStrategy::Fixed, 0));
+        let header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0xe1, 0x88, 0xb4, 0x0f, 0x20, 0xbd]);
+        assert_eq!((header.blocking_strategy, header.coded_number), (BlockingStrategy::Fixed, 0x1234));
+        assert_eq!(header.block_size, 16);
+        let header = read_header(&[0xff, 0xf9, 0x6c, 0x18, 0xfc, 0x92, 0x8d, 0x85, 0x99, 0xb8, 0x0f, 0x20, 0x3b]);
+        assert_eq!((header.blocking_strategy, header.coded_number), (BlockingStrategy::Variable, 0x1234_5678));
+        assert_eq!(header.sample_number, 0x1234_5678);
+        // the largest in 36 bits
+        let header = read_header(&[0xff, 0xf9, 0x6c, 0x18, 0xfe, 0xbf, 0xbf, 0xbf, 0xbf, 0xbf, 0xbf, 0x0f, 0x20, 0xa3]);
+        assert_eq!(header.coded_number, 0xf_ffff_ffff);
+        assert_eq!(header.sample_rate, 32000);
+    }
+
+    #[test]
+    fn test_utf8_coded_lengths() {
+        // the largest number of each length, followed by a byte left unread
+        let cases: &[(&[u8], u64)] = &[
+            (&[0x7f], 0x7f),
+            (&[0xdf, 0xbf], 0x7ff),
+            (&[0xef, 0xbf, 0xbf], 0xffff),
+            (&[0xf7, 0xbf, 0xbf, 0xbf], 0x1f_ffff),
+            (&[0xfb, 0xbf, 0xbf, 0xbf, 0xbf], 0x3ff_ffff),
+            (&[0xfd, 0xbf, 0xbf, 0xbf, 0xbf, 0xbf], 0x7fff_ffff),
+            (&[0xfe, 0x82, 0x80, 0x80, 0x80, 0x80, 0x81], 0x8000_0001)
+        ];
+        for &(coded, expected) in cases {
+            let bytes = [coded, &[0x5a]].concat();
+            let mut bytes: &[u8] = &bytes;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            assert_eq!(read_utf8_coded(&mut reader).unwrap(), expected);
+            assert_eq!(reader.read_u8().unwrap(), 0x5a);
+        }
+    }
+
+    #[test]
+    fn test_coded_number_invalid() {
+        // a continuation byte at the beginning, and a sequence cut short
+        let headers: &[&[u8]] = &[&[0xff, 0xf8, 0x6c, 0x18, 0x80], &[0xff, 0xf8, 0x6c, 0x18, 0xe1, 0x08]];
+        for &bytes in headers {
+            match read_header_with_options(bytes, &DecodeOptions::default()).unwrap_err().code() {
+                ErrorCode::FrameNumberInvalid => (),
+                code => panic!("unexpected error {:?}", code)
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserved_bits() {
+        let header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+        assert!(!header.reserved_bits_set);
+        // the bit following the sample size, and the one following the sync code
+        let headers: &[&[u8]] = &[
+            &[0xff, 0xf8, 0x6c, 0x19, 0x00, 0x0f, 0x20, 0xec],
+            &[0xff, 0xfa, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xa8]
+        ];
+        for &bytes in headers {
+            let header = read_header(bytes);
+            assert!(header.reserved_bits_set);
+            assert_eq!(header.sample_rate, 32000);
+            match read_header_with_options(bytes, &DecodeOptions::strict()).unwrap_err().code() {
+                ErrorCode::FrameOutOfSync => (),
+                code => panic!("unexpected error {:?}", code)
+            }
+        }
+    }
+
+    // the header of a 16-bit stereo frame of 16 samples with the sample rate code
+    fn header_with_sample_rate(code: u8) -> Vec<u8> {
+        use super::super::crc::HasherCrc8;
+        let mut bytes = vec![0xff, 0xf8, 0x60 | code, 0x18, 0x00, 0x0f];
+        let mut hasher = HasherCrc8::new();
+        hasher.hash(&bytes);
+        bytes.push(hasher.state());
+        bytes
+    }
+
+    #[test]
+    fn test_sample_rate_table() {
+        // the first is of STREAMINFO
+        let rates = [44100, 88200, 176400, 192000, 8000, 16000, 22050, 24000, 32000, 44100, 48000, 96000];
+        for (code, &rate) in rates.iter().enumerate() {
+            let header = read_header(&header_with_sample_rate(code as u8));
+            assert_eq!(header.sample_rate, rate);
+        }
+        // invalid, resolved as not coded
+        let bytes = header_with_sample_rate(0b1111);
+        assert_eq!(read_header(&bytes).sample_rate, 44100);
+        match read_header_with_options(&bytes, &DecodeOptions::strict()).unwrap_err().code() {
+            ErrorCode::FrameOutOfSync => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_sample_rate_mismatch() {
+        // the stream is 44.1 kHz
+        let bytes = header_with_sample_rate(0b1001);
+        assert!(read_header_with_options(&bytes, &DecodeOptions::strict()).is_ok());
+        let bytes = header_with_sample_rate(0b1010);
+        assert_eq!(read_header(&bytes).sample_rate, 48000);
+        match read_header_with_options(&bytes, &DecodeOptions::strict()).unwrap_err().code() {
+            ErrorCode::FrameSampleRateMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        // not coded
+        let bytes = header_with_sample_rate(0b0000);
+        assert_eq!(read_header_with_options(&bytes, &DecodeOptions::strict()).unwrap().sample_rate, 44100);
+    }
+
+    #[test]
+    fn test_sample_rate_in_khz() {
+        let header = read_header(&[0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa]);
+        assert_eq!(header.block_size, 16);
+        assert_eq!(header.sample_rate, 32000);
+    }
+
+    #[test]
+    fn test_sample_rate_in_hz() {
+        let header = read_header(&[0xff, 0xf8, 0x6d, 0x18, 0x00, 0x0f, 0x56, 0x23, 0xf4]);
+        assert_eq!(header.sample_rate, 22051);
+    }
+
+    #[test]
+    fn test_sample_rate_in_tens_of_hz() {
+        let header = read_header(&[0xff, 0xf8, 0x6e, 0x18, 0x00, 0x0f, 0x11, 0x3a, 0xf0]);
+        assert_eq!(header.sample_rate, 44100);
+    }
+
+    #[test]
+    fn test_silent_frame() {
+        use super::super::crc::HasherCrc16Buypass;
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut stream_proxy = DecodingReadProxy::new(&mut bytes);
+        let mut stream_reader = BitReader::new(&mut stream_proxy);
+        let stream = Stream::new(&mut stream_reader).unwrap();
+        // 16 samples of independent stereo, each channel coded as a constant
+        let frame_bytes = |left: u16, right: u16| -> Vec<u8> {
+            let mut bytes = vec![0xff, 0xf8, 0x6c, 0x18, 0x00, 0x0f, 0x20, 0xfa];
+            bytes.push(0x00);
+            bytes.extend_from_slice(&left.to_be_bytes());
+            bytes.push(0x00);
+            bytes.extend_from_slice(&right.to_be_bytes());
+            let mut hasher = HasherCrc16Buypass::new();
+            hasher.hash(&bytes);
+            bytes.extend_from_slice(&hasher.state().to_be_bytes());
+            bytes
+        };
+        let expectations: &[(u16, u16, bool)] = &[(0, 0, true), (0, 0xfffb, false)];
+        for &(left, right, silent) in expectations {
+            let data = frame_bytes(left, right);
+            let mut bytes: &[u8] = &data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(), FrameBuffer::new()];
+            let frame = Frame::from_reader(&mut reader, &stream.stream_info, &DecodeOptions::default(), &mut blocks)
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.stats.subframes, vec![
+                SubframeStats { method: PredictionMethod::Constant, constant: Some(left as i16 as i64), warm_up: 0, wasted_bits: 0, residual: None },
+                SubframeStats { method: PredictionMethod::Constant, constant: Some(right as i16 as i64), warm_up: 0, wasted_bits: 0, residual: None }
+            ]);
+            assert_eq!(frame.stats.is_silent(), silent);
+            assert_eq!(frame.blocks[1][..], vec![right as i16 as i32; 16][..]);
+        }
+    }
+
+    // packs values of the given widths in bits from the most significant bit, padding zeros at the end
+    fn pack_bits(values: &[(u64, usize)]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut count: usize = 0;
+        for &(value, width) in values {
+            for i in (0..width).rev() {
+                if count.is_multiple_of(8) {
+                    bytes.push(0);
+                }
+                let bit = ((value >> i) & 1) as u8;
+                *bytes.last_mut().unwrap() |= bit << (7 - count % 8);
+                count += 1;
+            }
+        }
+        bytes
+    }
+
+    // decodes a frame of 16 samples in 16-bit independent stereo
+    fn decode_frame_bytes(subframes: &[(u64, usize)]) -> Result<Vec<Vec<i32>>> {
+        decode_frame_bytes_as(0x18, subframes)
+    }
+
+    // with the channel assignment and the sample size given in the 4th byte of the header
+    fn decode_frame_bytes_as(channels_and_size: u8, subframes: &[(u64, usize)]) -> Result<Vec<Vec<i32>>> {
+        use super::super::crc::{HasherCrc8, HasherCrc16Buypass};
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut stream_proxy = DecodingReadProxy::new(&mut bytes);
+        let mut stream_reader = BitReader::new(&mut stream_proxy);
+        let stream = Stream::new(&mut stream_reader).unwrap();
+        let mut data = vec![0xff, 0xf8, 0x6c, channels_and_size, 0x00, 0x0f, 0x20];
+        let mut hasher = HasherCrc8::new();
+        hasher.hash(&data);
+        data.push(hasher.state());
+        data.extend(pack_bits(subframes));
+        let mut hasher = HasherCrc16Buypass::new();
+        hasher.hash(&data);
+        data.extend_from_slice(&hasher.state().to_be_bytes());
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut blocks: Vec<FrameBuffer> = vec![FrameBuffer::new(), FrameBuffer::new()];
+        let frame = Frame::from_reader(&mut reader, &stream.stream_info, &DecodeOptions::default(), &mut blocks)?
+            .unwrap();
+        Ok(frame.blocks.iter().map(|x| x.to_vec()).collect())
+    }
+
+    #[test]
+    fn test_32bit_side_channel() {
+        let left: Vec<i32> = (0..16).map(|i| i32::MAX - i).collect();
+        let right: Vec<i32> = (0..16).map(|i| i32::MIN + i).collect();
+        // VERBATIM left in 32 bits, then VERBATIM side taking 33 bits
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b000001, 6), (0, 1)];
+        bits.extend(left.iter().map(|&x| (x as u32 as u64, 32)));
+        bits.extend_from_slice(&[(0, 1), (0b000001, 6), (0, 1)]);
+        bits.extend(left.iter().zip(&right).map(|(&l, &r)| ((l as i64 - r as i64) as u64 & 0x1_ffff_ffff, 33)));
+        // left-side stereo in 32 bits
+        let blocks = decode_frame_bytes_as(0b1000_1110, &bits).unwrap();
+        assert_eq!(blocks, vec![left, right]);
+    }
+
+    #[test]
+    fn test_block_size_exceeds_max() {
+        use super::super::crc::HasherCrc8;
+        // 8-bit block sizes of 64 and 65 in a stream of 64 at most
+        for &(size, valid) in &[(0x3f, true), (0x40, false)] {
+            let mut bytes = vec![0xff, 0xf8, 0x6c, 0x18, 0x00, size, 0x20];
+            let mut hasher = HasherCrc8::new();
+            hasher.hash(&bytes);
+            bytes.push(hasher.state());
+            match read_header_with_options(&bytes, &DecodeOptions::default()) {
+                Ok(header) => assert!(valid && header.block_size == 64),
+                Err(e) => match e.code() {
+                    ErrorCode::FrameBlockSizeExceedsMax => assert!(!valid),
+                    code => panic!("unexpected error {:?}", code)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_channel_count_mismatch() {
+        // 3 channels and monaural in a stereo stream
+        for &channels_and_size in &[0x28, 0x08] {
+            match decode_frame_bytes_as(channels_and_size, &[]).unwrap_err().code() {
+                ErrorCode::FrameChannelCountMismatch => (),
+                code => panic!("unexpected error {:?}", code)
+            }
+        }
+    }
+
+    #[test]
+    fn test_escaped_residuals() {
+        let residuals: Vec<i64> = vec![0, 1, -1, 31, -32, 5, -5, 12, 0, 0, 7, -7, 30, -31, 2, -2];
+        // FIXED order 1 from a warm-up sample of 100
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b001001, 6), (0, 1), (100, 16)];
+        // partitioned rice with a single partition escaped to 6 bits
+        bits.extend_from_slice(&[(0b00, 2), (0b0000, 4), (0b1111, 4), (6, 5)]);
+        bits.extend(residuals[1..].iter().map(|&x| ((x as u64) & 0x3f, 6)));
+        // FIXED order 0 with escaped residuals of 0 bits, that is silence
+        bits.extend_from_slice(&[(0, 1), (0b001000, 6), (0, 1), (0b00, 2), (0b0000, 4), (0b1111, 4), (0, 5)]);
+        let blocks = decode_frame_bytes(&bits).unwrap();
+        let mut expected: Vec<i32> = vec![100];
+        for &x in &residuals[1..] {
+            let last = *expected.last().unwrap();
+            expected.push(last + x as i32);
+        }
+        assert_eq!(blocks[0], expected);
+        assert_eq!(blocks[1], vec![0; 16]);
+    }
+
+    #[test]
+    fn test_restore_overflow() {
+        // FIXED order 1 from a warm-up sample of 32767, followed by residuals escaped to 2 bits
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b001001, 6), (0, 1), (32767, 16)];
+        bits.extend_from_slice(&[(0b00, 2), (0b0000, 4), (0b1111, 4), (2, 5)]);
+        let silence = [(0, 1), (0b000000, 6), (0, 1), (0, 16)];
+        // -1 is within 16 bits
+        let mut valid = bits.clone();
+        valid.extend((1..16).map(|_| (0b11, 2)));
+        valid.extend_from_slice(&silence);
+        let blocks = decode_frame_bytes(&valid).unwrap();
+        assert_eq!(blocks[0][15], 32767 - 15);
+        // 1 exceeds 16 bits
+        bits.extend((1..16).map(|_| (0b01, 2)));
+        bits.extend_from_slice(&silence);
+        match decode_frame_bytes(&bits).unwrap_err().code() {
+            ErrorCode::LPCSignalRestoreFailure => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_restore_overflow_wrapping() {
+        // 24 bits, FIR order 1 from a warm-up sample of 2^22 with a coefficient in 12 bits and
+        // no shift, followed by residuals of 5, -5120 and then 0 escaped to 14 bits. with the
+        // coefficient of 2^10, the prediction of 2^32 wraps to 0 in 32 bits, restoring 5 and
+        // then 0 throughout, all within 24 bits.
+        let fir = |coefficient: u64| {
+            let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b100000, 6), (0, 1), (1 << 22, 24)];
+            bits.extend_from_slice(&[(0b1011, 4), (0, 5), (coefficient, 12)]);
+            bits.extend_from_slice(&[(0b00, 2), (0b0000, 4), (0b1111, 4), (14, 5)]);
+            bits.extend_from_slice(&[(5, 14), (-5120i32 as u64 & 0x3fff, 14)]);
+            bits.extend((3..16).map(|_| (0, 14)));
+            bits.extend_from_slice(&[(0, 1), (0b000000, 6), (0, 1), (0, 24)]);
+            bits
+        };
+        let blocks = decode_frame_bytes_as(0x1c, &fir(1)).unwrap();
+        assert_eq!(blocks[0][15], (1 << 22) + 5 - 5120);
+        match decode_frame_bytes_as(0x1c, &fir(1 << 10)).unwrap_err().code() {
+            ErrorCode::LPCSignalRestoreFailure => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_residual_partition_mismatch() {
+        let silence = [(0, 1), (0b000000, 6), (0, 1), (0, 16)];
+        // FIXED order 1 with 16 partitions of a single residual, leaving none for the first
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b001001, 6), (0, 1), (7, 16), (0b00, 2), (4, 4)];
+        bits.extend((0..16).map(|i| if i == 0 { (0, 4) } else { (0b1, 1 + 4) }));
+        bits.extend_from_slice(&silence);
+        let blocks = decode_frame_bytes(&bits).unwrap();
+        assert_eq!(blocks[0], vec![7; 16]);
+        // 32 partitions do not divide the 16 samples
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b001001, 6), (0, 1), (7, 16), (0b00, 2), (5, 4)];
+        bits.extend((0..32).map(|_| (0, 4)));
+        bits.extend_from_slice(&silence);
+        match decode_frame_bytes(&bits).unwrap_err().code() {
+            ErrorCode::ResidualPartitionMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        // FIXED order 2 with partitions of a single residual, less than the warm-up samples
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b001010, 6), (0, 1), (7, 16), (7, 16), (0b00, 2), (4, 4)];
+        bits.extend((0..16).map(|_| (0, 4)));
+        bits.extend_from_slice(&silence);
+        match decode_frame_bytes(&bits).unwrap_err().code() {
+            ErrorCode::ResidualPartitionMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_wasted_bits() {
+        // CONSTANT of 5 in 14 bits with 2 wasted bits
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b000000, 6), (1, 1), (0b01, 2), (5, 14)];
+        // VERBATIM in 13 bits with 3 wasted bits
+        bits.extend_from_slice(&[(0, 1), (0b000001, 6), (1, 1), (0b001, 3)]);
+        let samples: Vec<i64> = (0..16).map(|x| x * 300 - 2000).collect();
+        bits.extend(samples.iter().map(|&x| ((x as u64) & 0x1fff, 13)));
+        let blocks = decode_frame_bytes(&bits).unwrap();
+        assert_eq!(blocks[0], vec![20; 16]);
+        let expected: Vec<i32> = samples.iter().map(|&x| (x * 8) as i32).collect();
+        assert_eq!(blocks[1], expected);
+    }
+
+    #[test]
+    fn test_malformed_subframes() {
+        let error = |bits: &[(u64, usize)]| decode_frame_bytes(bits).unwrap_err();
+        // CONSTANT with more bits wasted than the sample size
+        assert!(matches!(error(&[(0, 1), (0b000000, 6), (1, 1), (0, 16), (1, 1)]).code(), ErrorCode::SubframeWastedBitsInvalid));
+        assert!(matches!(error(&[(0, 1), (0b000000, 6), (1, 1), (0, 64), (1, 1)]).code(), ErrorCode::SubframeWastedBitsInvalid));
+        // FIR order 32 exceeding the block size of 16
+        assert!(matches!(error(&[(0, 1), (0b111111, 6), (0, 1)]).code(), ErrorCode::SubframeOrderExceedsBlockSize));
+        // reserved FIXED order 5
+        assert!(matches!(error(&[(0, 1), (0b001101, 6), (0, 1)]).code(), ErrorCode::FixedLPCCoefficientUnknown));
+    }
 
     #[test]
-    fn test_sign_extend() {
-        assert_eq!(sign_extend(0b110, 3), -2);
-        assert_eq!(sign_extend(0b10110011, 8), -77);
-        assert_eq!(sign_extend(0b001, 3), 1);
-        assert_eq!(sign_extend(0b00110011, 8), 51);
+    fn test_all_bits_wasted() {
+        // CONSTANT with all 16 bits wasted, having no bits left for the value
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b000000, 6), (1, 1), (1, 16)];
+        // FIXED order 2 with all 16 bits wasted, of zero residuals in a partition of parameter 0
+        bits.extend_from_slice(&[(0, 1), (0b001010, 6), (1, 1), (1, 16), (0b00, 2), (0, 4), (0, 4)]);
+        bits.extend((0..14).map(|_| (1, 1)));
+        let blocks = decode_frame_bytes(&bits).unwrap();
+        assert_eq!(blocks, vec![vec![0; 16], vec![0; 16]]);
+        // nonzero residuals exceed the range of zero bits
+        let mut bits: Vec<(u64, usize)> = vec![(0, 1), (0b000000, 6), (1, 1), (1, 16)];
+        bits.extend_from_slice(&[(0, 1), (0b001010, 6), (1, 1), (1, 16), (0b00, 2), (0, 4), (0, 4)]);
+        bits.extend((0..14).map(|_| (0b01, 2)));
+        assert!(matches!(decode_frame_bytes(&bits).unwrap_err().code(), ErrorCode::LPCSignalRestoreFailure));
     }
 }
@@ -0,0 +1,181 @@
+use alloc::vec::Vec;
+use super::error::Result;
+use super::decode::Decode;
+use super::frame::{Frame, FrameBuffer, FrameHeader, FrameStats, FrameFooter};
+use super::stream::Stream;
+
+// a frame owning its samples, one block per channel.
+// unlike `Frame`, it can be kept around after decoding the next frame
+#[derive(Debug, Clone)]
+pub struct DecodedFrame {
+    pub header: FrameHeader,
+    pub stats: FrameStats,
+    pub footer: FrameFooter,
+    pub blocks: Vec<Vec<i32>>
+}
+
+impl<'a> From<&Frame<'a>> for DecodedFrame {
+    fn from(frame: &Frame<'a>) -> Self {
+        let blocks = frame.blocks.iter()
+            .map(|x| x.to_vec())
+            .collect();
+        DecodedFrame {
+            header: frame.header.clone(),
+            stats: frame.stats.clone(),
+            footer: frame.footer,
+            blocks
+        }
+    }
+}
+
+// decodes a frame on every call to `next`, reusing the buffers across frames.
+// iteration stops after the end of stream or the first error.
+// NOTE: unlike `Stream::decode_frames`, the MD5 signature is not verified
+pub struct FrameIter<'a> {
+    stream: &'a Stream,
+    reader: &'a mut dyn Decode,
+    blocks: Vec<FrameBuffer>,
+    finished: bool
+}
+
+impl<'a> FrameIter<'a> {
+    pub fn new(stream: &'a Stream, reader: &'a mut dyn Decode) -> Self {
+        let blocks: Vec<FrameBuffer> = stream.allocate_blocks();
+        FrameIter {
+            stream,
+            reader,
+            blocks,
+            finished: false
+        }
+    }
+}
+
+impl<'a> Iterator for FrameIter<'a> {
+    type Item = Result<DecodedFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None
+        }
+        let frame = match Frame::from_reader(self.reader, &self.stream.stream_info, &self.stream.options, &mut self.blocks) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                self.finished = true;
+                return None
+            },
+            Err(e) => {
+                self.finished = true;
+                return Some(Err(e))
+            }
+        };
+        Some(Ok(DecodedFrame::from(&frame)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use super::super::bits::BitReader;
+    use super::super::decode::{DecodingRead, DecodingReadProxy};
+    use super::super::error::ErrorCode;
+
+    static STEREO16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+
+    #[test]
+    fn test_frames() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut expected: Vec<Vec<Vec<i32>>> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            expected.push(frame.blocks.iter().map(|x| x.to_vec()).collect());
+        }).unwrap();
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let frames: Vec<DecodedFrame> = stream.frames(&mut reader)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[4].header.block_size, 40);
+        assert_eq!(frames[1].stats.byte_offset, 42 + 141);
+        let blocks: Vec<Vec<Vec<i32>>> = frames.into_iter().map(|x| x.blocks).collect();
+        assert_eq!(blocks, expected);
+    }
+
+    #[test]
+    fn test_frame_extents() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let extents: Vec<(u64, u64)> = stream.frames(&mut reader)
+            .map(|x| x.map(|x| (x.stats.byte_offset, x.stats.byte_len)))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(extents, vec![(42, 141), (183, 199), (382, 215), (597, 167), (764, 145)]);
+        // each frame ends where the next one begins, the last at the end of stream
+        for pair in extents.windows(2) {
+            assert_eq!(pair[0].0 + pair[0].1, pair[1].0);
+        }
+        assert_eq!(extents[4].0 + extents[4].1, STEREO16.len() as u64);
+    }
+
+    #[test]
+    fn test_decoded_frame_from() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        // buffered out of the streaming decoder reusing its blocks
+        let mut frames: Vec<DecodedFrame> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            frames.push(DecodedFrame::from(frame));
+        }).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0].header.channel_assignment, frames[4].header.channel_assignment);
+        assert_eq!(frames[4].blocks[0].len(), 40);
+        assert_eq!(frames[3].stats.byte_offset, 42 + 555);
+        assert_ne!(frames[0].blocks, frames[1].blocks);
+    }
+
+    #[test]
+    fn test_frames_stop_early() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut count: usize = 0;
+        for frame in stream.frames(&mut reader) {
+            let frame = frame.unwrap();
+            count += 1;
+            if frame.header.coded_number == 1 {
+                break;
+            }
+        }
+        assert_eq!(count, 2);
+        // the reader is left at the third frame
+        assert_eq!(reader.byte_offset(), 42 + 340);
+    }
+
+    #[test]
+    fn test_frames_error() {
+        let mut corrupted = STEREO16.to_vec();
+        // in the second frame
+        corrupted[42 + 150] ^= 0x01;
+        let mut bytes: &[u8] = &corrupted;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut frames = stream.frames(&mut reader);
+        assert!(frames.next().unwrap().is_ok());
+        match frames.next().unwrap().unwrap_err().code() {
+            ErrorCode::FrameCrcMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        assert!(frames.next().is_none());
+    }
+}
@@ -0,0 +1,30 @@
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod io;
+pub mod bits;
+pub mod error;
+pub mod stream;
+pub mod metadata;
+pub mod bitvec;
+pub mod frame;
+pub mod crc;
+pub mod decode;
+pub mod slice;
+pub mod chunked;
+pub mod iter;
+pub mod sample;
+pub mod options;
+#[cfg(feature = "std")]
+pub mod downmix;
+pub mod spec;
+pub mod chain;
+pub mod ogg;
+#[cfg(feature = "std")]
+pub mod file;
+
+pub use stream::decode_bytes;
+#[cfg(feature = "std")]
+pub use file::{decode_file_i16, FlacReader};
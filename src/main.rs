@@ -1,70 +1,59 @@
 
-extern crate itertools;
-extern crate hound;
 
-mod bits;
-mod error;
-mod stream;
-mod metadata;
-mod bitvec;
-mod frame;
-mod crc;
-mod decode;
-
-use std::fs::File;
-use std::io::BufReader;
-
-use error::Result;
-use bits::BitReader;
-use decode::DecodingReadProxy;
-use frame::Frame;
-use stream::Stream;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use suono::frame::Frame;
+use suono::FlacReader;
 
 // a usage example converting .flac to .wav
-fn decode_to_wav() -> Result<()> {
-    let file = File::open("input.flac").unwrap();
-    let mut buf = BufReader::new(file);
-    // some setup for our decoder
-    let mut proxy = DecodingReadProxy::new(&mut buf);
-    let mut reader = BitReader::new(&mut proxy);
+fn decode_to_wav() -> Result<(), Box<dyn Error>> {
     // start reading FLAC stream
-    let stream = Stream::new(&mut reader)?;
-    let info = stream.stream_info;
+    let mut flac = FlacReader::open("input.flac")?;
+    let info = *flac.stream_info();
     println!("{:?}", info);
     // writer setup
-    let spec = hound::WavSpec {
-        channels: info.number_of_channels as u16,
-        sample_rate: info.sample_rate as u32,
-        bits_per_sample: info.bits_per_sample as u16,
-        sample_format: hound::SampleFormat::Int,
-    };
-    let writer = &mut hound::WavWriter::create("output.wav", spec).unwrap();
-    // frame processing
-    let frame_sink = |frame: &Frame| {
-        match frame.blocks.len() {
-            2 => {
-                // stereo
-                let left = &frame.blocks[0];
-                let right = &frame.blocks[1];
-                for sample in itertools::interleave(left, right) {
-                    writer.write_sample(*sample).unwrap();
-                }
-            },
-            1 => {
-                // monaural
-                for sample in &frame.blocks[0] {
-                    writer.write_sample(*sample).unwrap();
-                }
-            },
-            _ => unreachable!()
-        }
+    let spec: hound::WavSpec = info.spec().into();
+    let mut writer = hound::WavWriter::create("output.wav", spec)?;
+    // frame processing, where depths such as 20 bits are left-justified as WAV expects.
+    // an error writing aborts decoding
+    let frame_sink = |frame: &Frame| -> Result<(), Box<dyn Error>> {
+        frame.write_to_samples(&mut writer)?;
+        Ok(())
     };
     println!("decoding frames...");
-    stream.decode_frames(&mut reader, frame_sink)?;
+    flac.try_decode_frames(frame_sink)?;
+    writer.finalize()?;
+    // the channels are interleaved in the order of FLAC, e.g. front left, front right, front
+    // center, LFE, back left and back right for 5.1. hound writes a default channel mask of the
+    // lowest bits, which is replaced with the mask of the FLAC layout
+    if info.number_of_channels > 2 {
+        set_wav_channel_mask("output.wav", info.channel_layout().wav_channel_mask())?;
+    }
     println!("done");
     Ok(())
 }
 
+// overwrites dwChannelMask of the WAVE_FORMAT_EXTENSIBLE header hound writes for more than
+// 2 channels, which lies 40 bytes into the file when the `fmt ` chunk comes first
+fn set_wav_channel_mask(path: &str, mask: u32) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut header = [0u8; 40];
+    file.read_exact(&mut header)?;
+    let fmt_size = u32::from_le_bytes([header[16], header[17], header[18], header[19]]);
+    let format_tag = u16::from_le_bytes([header[20], header[21]]);
+    let extensible = &header[..4] == b"RIFF" && &header[8..16] == b"WAVEfmt "
+        && fmt_size >= 40 && format_tag == 0xfffe;
+    if !extensible {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a WAVE_FORMAT_EXTENSIBLE header"))
+    }
+    file.seek(SeekFrom::Start(40))?;
+    file.write_all(&mask.to_le_bytes())
+}
+
 fn main() {
-    decode_to_wav().unwrap();
+    if let Err(e) = decode_to_wav() {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
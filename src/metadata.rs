@@ -1,9 +1,14 @@
 
-use super::decode::Decode;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::time::Duration;
+use super::decode::{Decode, DecodingReadProxy};
 use super::error::{Error, ErrorCode, Result};
-use super::bitvec::Bitvec;
+use super::bits::BitReader;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MetadataType {
     StreamInfo,
     Padding,
@@ -32,7 +37,8 @@ impl MetadataType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MetadataHeader {
     pub last: bool,
     pub metadata_type: MetadataType,
@@ -40,28 +46,28 @@ pub struct MetadataHeader {
 }
 
 impl MetadataHeader {
-    pub fn from_reader(reader: &mut Decode) -> Result<Self> {
+    pub fn from_reader(reader: &mut dyn Decode) -> Result<Self> {
         let last      = reader.read_bool()?;
         let type_bits = reader.read_u8_bits(7)?;
-        let length    = reader.read_u32_bits(24)?;
+        let length    = reader.read_u24()?;
         let metadata_type = MetadataType::parse(type_bits)
             .ok_or_else(|| Error::from_code(ErrorCode::InvalidMetadataType))?;
         let header = MetadataHeader {
-            last: last,
-            metadata_type: metadata_type,
+            last,
+            metadata_type,
             length_in_bytes: length as usize
         };
         Ok(header)
     }
 
-    pub fn skip_body(&self, reader: &mut Decode) -> Result<()> {
-        let mut vec = Bitvec::new();
-        reader.read_bitvec(&mut vec, self.length_in_bytes * 8)?;
+    pub fn skip_body(&self, reader: &mut dyn Decode) -> Result<()> {
+        reader.skip_bits(self.length_in_bytes * 8)?;
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StreamInfo {
     pub min_block_size: usize,
     pub max_block_size: usize,
@@ -71,20 +77,28 @@ pub struct StreamInfo {
     pub number_of_channels: usize,
     pub bits_per_sample: usize,
     pub total_samples: usize,
+    // the MD5 of the samples, serialized as 32 hex digits as the integer exceeds most formats
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_signature"))]
     pub signature: u128,
 }
 
+#[cfg(feature = "serde")]
+fn serialize_signature<S: serde::Serializer>(signature: &u128, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{:032x}", signature))
+}
+
 impl StreamInfo {
-    pub fn from_reader(reader: &mut Decode) -> Result<Self> {
+    pub fn from_reader(reader: &mut dyn Decode) -> Result<Self> {
         let min_block_size  = reader.read_u16()?;
         let max_block_size  = reader.read_u16()?;
-        let min_frame_size  = reader.read_u32_bits(24)?;
-        let max_frame_size  = reader.read_u32_bits(24)?;
+        let min_frame_size  = reader.read_u24()?;
+        let max_frame_size  = reader.read_u24()?;
         let sample_rate     = reader.read_u32_bits(20)?;
         let channels        = reader.read_u8_bits(3)?;
         let bits_per_sample = reader.read_u8_bits(5)?;
         let total_samples   = reader.read_u64_bits(36)?;
         let signature       = reader.read_u128()?;
+        let bits_per_sample = (bits_per_sample as usize) + 1;
         let stream_info = StreamInfo {
             min_block_size: min_block_size as usize,
             max_block_size: max_block_size as usize,
@@ -92,10 +106,686 @@ impl StreamInfo {
             max_frame_size: max_frame_size as usize,
             sample_rate: sample_rate as usize,
             number_of_channels: (channels as usize) + 1,
-            bits_per_sample: (bits_per_sample as usize) + 1,
+            bits_per_sample,
             total_samples: total_samples as usize,
-            signature: signature,
+            signature,
         };
+        stream_info.validate()?;
         Ok(stream_info)
     }
+
+    // reads the magic, skipping an ID3v2 tag, and the STREAMINFO block from the beginning of the stream, which is
+    // mandated to come first, without walking the rest of the metadata as `Stream::new` does.
+    // the reader is left at the block following STREAMINFO.
+    pub fn read_from(reader: &mut dyn Decode) -> Result<Self> {
+        read_magic(reader)?;
+        let header = MetadataHeader::from_reader(reader)?;
+        if header.metadata_type != MetadataType::StreamInfo {
+            return Err(Error::from_code(ErrorCode::InvalidMetadataType))
+        }
+        if header.length_in_bytes != 34 {
+            return Err(Error::from_code(ErrorCode::MetadataLengthMismatch))
+        }
+        StreamInfo::from_reader(reader)
+    }
+
+    // checks the invariants the fields are not limited to by their widths,
+    // failing early on a corrupt STREAMINFO rather than on the frames
+    pub fn validate(&self) -> Result<()> {
+        // FLAC permits 4 to 32 bits per sample
+        if !(4..=32).contains(&self.bits_per_sample) {
+            return Err(Error::from_code(ErrorCode::InvalidBitDepth))
+        }
+        // frame sizes of 0 are unknown
+        let frame_sizes_known = self.min_frame_size != 0 && self.max_frame_size != 0;
+        let valid = self.min_block_size <= self.max_block_size
+            && self.sample_rate != 0
+            && (1..=8).contains(&self.number_of_channels)
+            && (!frame_sizes_known || self.min_frame_size <= self.max_frame_size);
+        if !valid {
+            return Err(Error::from_code(ErrorCode::InvalidStreamInfo))
+        }
+        Ok(())
+    }
+
+    // size of the decoded PCM in bytes, assuming each sample is packed into the fewest whole bytes
+    // little-endian and interleaved, e.g. 20-bit samples take 3 bytes. None if the total is unknown.
+    pub fn decoded_byte_len(&self) -> Option<u64> {
+        if self.total_samples == 0 {
+            return None
+        }
+        let bytes_per_sample = self.bits_per_sample.div_ceil(8) as u64;
+        Some((self.total_samples as u64) * (self.number_of_channels as u64) * bytes_per_sample)
+    }
+
+    // playback time of the whole stream. None if the total is unknown.
+    pub fn duration(&self) -> Option<Duration> {
+        if self.total_samples == 0 || self.sample_rate == 0 {
+            return None
+        }
+        let total_samples = self.total_samples as u64;
+        let sample_rate = self.sample_rate as u64;
+        let secs = total_samples / sample_rate;
+        let nanos = (total_samples % sample_rate) * 1_000_000_000 / sample_rate;
+        Some(Duration::new(secs, nanos as u32))
+    }
+
+    // the number of frames of a fixed-blocksize stream, which the last frame may be shorter in.
+    // None if either the total or the block size is unknown.
+    pub fn estimated_frame_count(&self) -> Option<u64> {
+        if self.total_samples == 0 || self.max_block_size == 0 {
+            return None
+        }
+        Some((self.total_samples as u64).div_ceil(self.max_block_size as u64))
+    }
+
+    pub fn channel_layout(&self) -> ChannelLayout {
+        ChannelLayout::from_channels(self.number_of_channels)
+    }
+
+    // the samples of each channel to reserve up front for decoding the whole stream. the total
+    // is only trusted up to about 20 seconds at 48kHz, as a forged one would allocate up to
+    // 2^36 samples, leaving the rest to grow as the frames are decoded.
+    pub fn reserved_samples(&self) -> usize {
+        self.total_samples.min(MAX_RESERVED_SAMPLES)
+    }
+}
+
+const MAX_RESERVED_SAMPLES: usize = 1 << 20;
+
+// the speaker assignment FLAC defines for each number of channels, where the channels
+// are in the listed order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelLayout {
+    Mono,
+    // left, right
+    Stereo,
+    // left, right, center
+    ThreePointZero,
+    // front left, front right, back left, back right
+    Quad,
+    // front left, front right, front center, back left, back right
+    FivePointZero,
+    // front left, front right, front center, LFE, back left, back right
+    FivePointOne,
+    // front left, front right, front center, LFE, back center, side left, side right
+    SixPointOne,
+    // front left, front right, front center, LFE, back left, back right, side left, side right
+    SevenPointOne
+}
+
+impl ChannelLayout {
+    pub fn from_channels(number_of_channels: usize) -> Self {
+        match number_of_channels {
+            1 => ChannelLayout::Mono,
+            2 => ChannelLayout::Stereo,
+            3 => ChannelLayout::ThreePointZero,
+            4 => ChannelLayout::Quad,
+            5 => ChannelLayout::FivePointZero,
+            6 => ChannelLayout::FivePointOne,
+            7 => ChannelLayout::SixPointOne,
+            // 3 bits in STREAMINFO can code no more than 8
+            _ => ChannelLayout::SevenPointOne
+        }
+    }
+
+    // the speaker positions as in dwChannelMask of WAVE_FORMAT_EXTENSIBLE. WAV interleaves the
+    // channels in the order of the bits, which the orders above follow, so the decoded samples
+    // are written as they are
+    pub fn wav_channel_mask(&self) -> u32 {
+        const FRONT_LEFT: u32 = 0x1;
+        const FRONT_RIGHT: u32 = 0x2;
+        const FRONT_CENTER: u32 = 0x4;
+        const LFE: u32 = 0x8;
+        const BACK_LEFT: u32 = 0x10;
+        const BACK_RIGHT: u32 = 0x20;
+        const BACK_CENTER: u32 = 0x100;
+        const SIDE_LEFT: u32 = 0x200;
+        const SIDE_RIGHT: u32 = 0x400;
+        let front = FRONT_LEFT | FRONT_RIGHT;
+        match self {
+            ChannelLayout::Mono => FRONT_CENTER,
+            ChannelLayout::Stereo => front,
+            ChannelLayout::ThreePointZero => front | FRONT_CENTER,
+            ChannelLayout::Quad => front | BACK_LEFT | BACK_RIGHT,
+            ChannelLayout::FivePointZero => front | FRONT_CENTER | BACK_LEFT | BACK_RIGHT,
+            ChannelLayout::FivePointOne => front | FRONT_CENTER | LFE | BACK_LEFT | BACK_RIGHT,
+            ChannelLayout::SixPointOne => front | FRONT_CENTER | LFE | BACK_CENTER | SIDE_LEFT | SIDE_RIGHT,
+            ChannelLayout::SevenPointOne => front | FRONT_CENTER | LFE | BACK_LEFT | BACK_RIGHT | SIDE_LEFT | SIDE_RIGHT
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeekPoint {
+    pub sample_number: u64,
+    // offset in bytes from the first byte of the first frame header
+    pub stream_offset: u64,
+    pub frame_samples: u16
+}
+
+impl SeekPoint {
+    const PLACEHOLDER: u64 = 0xffff_ffff_ffff_ffff;
+
+    pub fn placeholder() -> Self {
+        SeekPoint {
+            sample_number: SeekPoint::PLACEHOLDER,
+            stream_offset: 0,
+            frame_samples: 0
+        }
+    }
+
+    pub fn from_reader(reader: &mut dyn Decode) -> Result<Self> {
+        let sample_number = reader.read_u64()?;
+        let stream_offset = reader.read_u64()?;
+        let frame_samples = reader.read_u16()?;
+        let point = SeekPoint {
+            sample_number,
+            stream_offset,
+            frame_samples
+        };
+        Ok(point)
+    }
+
+    pub fn is_placeholder(&self) -> bool {
+        self.sample_number == SeekPoint::PLACEHOLDER
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeekTable {
+    pub points: Vec<SeekPoint>
+}
+
+impl SeekTable {
+    // each seek point occupies 18 bytes
+    const POINT_SIZE: usize = 18;
+
+    pub fn from_reader(reader: &mut dyn Decode, length_in_bytes: usize) -> Result<Self> {
+        let num_points = length_in_bytes / SeekTable::POINT_SIZE;
+        let mut points: Vec<SeekPoint> = Vec::with_capacity(num_points);
+        for _ in 0..num_points {
+            points.push(SeekPoint::from_reader(reader)?);
+        }
+        // skip the remainder if the length is not a multiple of the point size
+        let remainder = length_in_bytes % SeekTable::POINT_SIZE;
+        if remainder > 0 {
+            reader.skip_bits(remainder * 8)?;
+        }
+        Ok(SeekTable { points })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VorbisComment {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>
+}
+
+impl VorbisComment {
+    // NOTE: unlike the rest of FLAC, the integers are coded in little-endian
+    pub fn from_reader(reader: &mut dyn Decode, length_in_bytes: usize) -> Result<Self> {
+        let mut remaining = length_in_bytes;
+        let mut read_string = |reader: &mut dyn Decode| -> Result<String> {
+            let length = reader.read_u32_le()? as usize;
+            // overflows where usize is 32 bits
+            let total = length.checked_add(4)
+                .filter(|&x| x <= remaining)
+                .ok_or_else(|| Error::from_code(ErrorCode::MetadataLengthMismatch))?;
+            remaining -= total;
+            read_string(reader, length)
+        };
+        let vendor = read_string(reader)?;
+        let num_comments = reader.read_u32_le()?;
+        let mut comments: Vec<(String, String)> = Vec::new();
+        for _ in 0..num_comments {
+            let comment = read_string(reader)?;
+            // KEY=value
+            let mut pair = comment.splitn(2, '=');
+            let key = pair.next().unwrap_or("").to_string();
+            let value = pair.next().unwrap_or("").to_string();
+            comments.push((key, value));
+        }
+        let comment = VorbisComment {
+            vendor,
+            comments
+        };
+        Ok(comment)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Picture {
+    pub picture_type: u32,
+    pub mime_type: String,
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+    pub color_depth: u32,
+    // number of colors used for indexed-color pictures, 0 otherwise
+    pub colors: u32,
+    pub data: Vec<u8>
+}
+
+impl Picture {
+    pub fn from_reader(reader: &mut dyn Decode, length_in_bytes: usize) -> Result<Self> {
+        let read_length = |reader: &mut dyn Decode| -> Result<usize> {
+            let length = reader.read_u32()? as usize;
+            if length > length_in_bytes {
+                return Err(Error::from_code(ErrorCode::MetadataLengthMismatch))
+            }
+            Ok(length)
+        };
+        let picture_type = reader.read_u32()?;
+        let length       = read_length(reader)?;
+        let mime_type    = read_string(reader, length)?;
+        let length       = read_length(reader)?;
+        let description  = read_string(reader, length)?;
+        let width        = reader.read_u32()?;
+        let height       = reader.read_u32()?;
+        let color_depth  = reader.read_u32()?;
+        let colors       = reader.read_u32()?;
+        let length       = read_length(reader)?;
+        let data         = read_bytes(reader, length)?;
+        let picture = Picture {
+            picture_type,
+            mime_type,
+            description,
+            width,
+            height,
+            color_depth,
+            colors,
+            data
+        };
+        Ok(picture)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Application {
+    // registered application ID
+    pub id: [u8; 4],
+    pub data: Vec<u8>
+}
+
+impl Application {
+    pub fn from_reader(reader: &mut dyn Decode, length_in_bytes: usize) -> Result<Self> {
+        if length_in_bytes < 4 {
+            return Err(Error::from_code(ErrorCode::MetadataLengthMismatch))
+        }
+        let id = reader.read_u32()?.to_be_bytes();
+        let data = read_bytes(reader, length_in_bytes - 4)?;
+        Ok(Application { id, data })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueSheet {
+    pub media_catalog_number: String,
+    pub lead_in_samples: u64,
+    pub is_cd: bool,
+    pub tracks: Vec<CueSheetTrack>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueSheetTrack {
+    pub offset: u64,
+    pub number: u8,
+    pub isrc: String,
+    pub is_audio: bool,
+    pub pre_emphasis: bool,
+    pub indices: Vec<CueSheetIndex>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CueSheetIndex {
+    pub offset: u64,
+    pub number: u8
+}
+
+impl CueSheet {
+    pub fn from_reader(reader: &mut dyn Decode) -> Result<Self> {
+        let media_catalog_number = read_string(reader, 128)?;
+        let lead_in_samples      = reader.read_u64()?;
+        let is_cd                = reader.read_bool()?;
+        // 7 bits + 258 bytes reserved
+        reader.skip_bits(7 + 258 * 8)?;
+        let num_tracks = reader.read_u8()?;
+        let mut tracks: Vec<CueSheetTrack> = Vec::with_capacity(num_tracks as usize);
+        for _ in 0..num_tracks {
+            tracks.push(CueSheetTrack::from_reader(reader)?);
+        }
+        let cue_sheet = CueSheet {
+            media_catalog_number,
+            lead_in_samples,
+            is_cd,
+            tracks
+        };
+        Ok(cue_sheet)
+    }
+}
+
+impl CueSheetTrack {
+    fn from_reader(reader: &mut dyn Decode) -> Result<Self> {
+        let offset       = reader.read_u64()?;
+        let number       = reader.read_u8()?;
+        let isrc         = read_string(reader, 12)?;
+        let non_audio    = reader.read_bool()?;
+        let pre_emphasis = reader.read_bool()?;
+        // 6 bits + 13 bytes reserved
+        reader.skip_bits(6 + 13 * 8)?;
+        let num_indices = reader.read_u8()?;
+        let mut indices: Vec<CueSheetIndex> = Vec::with_capacity(num_indices as usize);
+        for _ in 0..num_indices {
+            let offset = reader.read_u64()?;
+            let number = reader.read_u8()?;
+            // 3 bytes reserved
+            reader.skip_bits(3 * 8)?;
+            indices.push(CueSheetIndex { offset, number });
+        }
+        let track = CueSheetTrack {
+            offset,
+            number,
+            isrc,
+            is_audio: !non_audio,
+            pre_emphasis,
+            indices
+        };
+        Ok(track)
+    }
+}
+
+// a metadata block whose body is kept unparsed until requested
+#[derive(Debug, Clone)]
+pub struct MetadataBlock {
+    pub header: MetadataHeader,
+    pub body: Vec<u8>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metadata {
+    StreamInfo(StreamInfo),
+    // length in bytes
+    Padding(usize),
+    Application(Application),
+    SeekTable(SeekTable),
+    VorbisComment(VorbisComment),
+    CueSheet(CueSheet),
+    Picture(Picture),
+    Unknown(Vec<u8>)
+}
+
+impl MetadataBlock {
+    pub fn from_reader(reader: &mut dyn Decode, header: MetadataHeader) -> Result<Self> {
+        let body = match header.metadata_type {
+            // nothing worth to keep
+            MetadataType::Padding => {
+                header.skip_body(reader)?;
+                vec![]
+            },
+            _ => read_bytes(reader, header.length_in_bytes)?
+        };
+        Ok(MetadataBlock { header, body })
+    }
+
+    pub fn parse(&self) -> Result<Metadata> {
+        let mut bytes: &[u8] = &self.body;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let reader = &mut reader;
+        let length = self.header.length_in_bytes;
+        let metadata = match self.header.metadata_type {
+            MetadataType::StreamInfo => Metadata::StreamInfo(StreamInfo::from_reader(reader)?),
+            MetadataType::Padding => Metadata::Padding(length),
+            MetadataType::Application => Metadata::Application(Application::from_reader(reader, length)?),
+            MetadataType::Seektable => Metadata::SeekTable(SeekTable::from_reader(reader, length)?),
+            MetadataType::VorbisComment => Metadata::VorbisComment(VorbisComment::from_reader(reader, length)?),
+            MetadataType::Cuesheet => Metadata::CueSheet(CueSheet::from_reader(reader)?),
+            MetadataType::Picture => Metadata::Picture(Picture::from_reader(reader, length)?),
+            MetadataType::Reserved => Metadata::Unknown(self.body.clone())
+        };
+        Ok(metadata)
+    }
+}
+
+// reads the "fLaC" marker at the beginning of the stream, skipping an ID3v2 tag some
+// taggers prepend to it
+pub(super) fn read_magic(reader: &mut dyn Decode) -> Result<()> {
+    let mut magic = reader.read_u32()?;
+    // "ID3" followed by the major version
+    if magic >> 8 == 0x494433 {
+        let _minor_version = reader.read_u8()?;
+        let flags = reader.read_u8()?;
+        // syncsafe, 7 bits in each byte
+        let size = reader.read_u32()?;
+        if size & 0x80808080 != 0 {
+            return Err(Error::from_code(ErrorCode::WrongMagic))
+        }
+        let size = (size & 0x7f) | ((size >> 1) & 0x3f80) | ((size >> 2) & 0x1fc000) | ((size >> 3) & 0xfe00000);
+        // the footer repeating the header of 10 bytes
+        let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+        reader.skip_bits((size as usize + footer) * 8)?;
+        magic = reader.read_u32()?;
+    }
+    if magic != 0x664c6143 {
+        return Err(Error::from_code(ErrorCode::WrongMagic))
+    }
+    Ok(())
+}
+
+fn read_bytes(reader: &mut dyn Decode, n: usize) -> Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![0; n];
+    for byte in &mut bytes[..] {
+        *byte = reader.read_u8()?;
+    }
+    Ok(bytes)
+}
+
+// reads a fixed-length string, dropping the trailing NUL padding
+fn read_string(reader: &mut dyn Decode, n: usize) -> Result<String> {
+    let bytes = read_bytes(reader, n)?;
+    let length = bytes.iter()
+        .rposition(|&x| x != 0)
+        .map(|x| x + 1)
+        .unwrap_or(0);
+    Ok(String::from_utf8_lossy(&bytes[..length]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::bits::BitReader;
+    use super::super::decode::{DecodingRead, DecodingReadProxy};
+
+    fn stream_info_bytes(bits_per_sample: u8) -> Vec<u8> {
+        let coded = bits_per_sample - 1;
+        let mut bytes: Vec<u8> = vec![0x10, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0x0a, 0xc4];
+        // 44100Hz, stereo
+        bytes.push(0x40 | (1 << 1) | (coded >> 4));
+        bytes.push((coded & 0xf) << 4);
+        bytes.resize(34, 0);
+        bytes
+    }
+
+    fn read_stream_info(bytes: &[u8]) -> Result<StreamInfo> {
+        let mut bytes: &[u8] = bytes;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        StreamInfo::from_reader(&mut reader)
+    }
+
+    #[test]
+    fn test_stream_info_bit_depth() {
+        let info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.number_of_channels, 2);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(read_stream_info(&stream_info_bytes(4)).unwrap().bits_per_sample, 4);
+        assert_eq!(read_stream_info(&stream_info_bytes(32)).unwrap().bits_per_sample, 32);
+    }
+
+    #[test]
+    fn test_read_from() {
+        let file: &[u8] = include_bytes!("../testdata/stereo16.flac");
+        let mut bytes: &[u8] = file;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let info = StreamInfo::read_from(&mut reader).unwrap();
+        assert_eq!(info.number_of_channels, 2);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.total_samples, 296);
+        assert_eq!(reader.byte_offset(), 42);
+        let read = |data: &[u8]| {
+            let mut bytes: &[u8] = data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            StreamInfo::read_from(&mut reader)
+        };
+        let mut data = file[..42].to_vec();
+        data[0] = b'F';
+        assert!(matches!(read(&data).unwrap_err().code(), ErrorCode::WrongMagic));
+        // padding first
+        let mut data = file[..42].to_vec();
+        data[4] = 0x81;
+        assert!(matches!(read(&data).unwrap_err().code(), ErrorCode::InvalidMetadataType));
+        let mut data = file[..42].to_vec();
+        data[7] = 0x21;
+        assert!(matches!(read(&data).unwrap_err().code(), ErrorCode::MetadataLengthMismatch));
+    }
+
+    #[test]
+    fn test_decoded_byte_len() {
+        let mut info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        assert_eq!(info.decoded_byte_len(), None);
+        info.total_samples = 1000;
+        assert_eq!(info.decoded_byte_len(), Some(4000));
+        info.bits_per_sample = 20;
+        assert_eq!(info.decoded_byte_len(), Some(6000));
+        info.bits_per_sample = 24;
+        assert_eq!(info.decoded_byte_len(), Some(6000));
+        info.bits_per_sample = 4;
+        assert_eq!(info.decoded_byte_len(), Some(2000));
+    }
+
+    #[test]
+    fn test_duration() {
+        let mut info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        // unknown
+        assert_eq!(info.duration(), None);
+        info.total_samples = 44100 * 3 + 22050;
+        assert_eq!(info.duration(), Some(Duration::from_millis(3500)));
+        info.total_samples = 1;
+        assert_eq!(info.duration(), Some(Duration::from_nanos(22675)));
+    }
+
+    #[test]
+    fn test_estimated_frame_count() {
+        let mut info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        // unknown
+        assert_eq!(info.estimated_frame_count(), None);
+        info.total_samples = 4096 * 3;
+        assert_eq!(info.estimated_frame_count(), Some(3));
+        info.total_samples = 4096 * 3 + 1;
+        assert_eq!(info.estimated_frame_count(), Some(4));
+        info.max_block_size = 0;
+        assert_eq!(info.estimated_frame_count(), None);
+    }
+
+    #[test]
+    fn test_channel_layout() {
+        let mut info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        assert_eq!(info.channel_layout(), ChannelLayout::Stereo);
+        info.number_of_channels = 1;
+        assert_eq!(info.channel_layout(), ChannelLayout::Mono);
+        info.number_of_channels = 6;
+        assert_eq!(info.channel_layout(), ChannelLayout::FivePointOne);
+        info.number_of_channels = 8;
+        assert_eq!(info.channel_layout(), ChannelLayout::SevenPointOne);
+        let masks: Vec<u32> = (1..=8).map(|x| ChannelLayout::from_channels(x).wav_channel_mask()).collect();
+        assert_eq!(masks, vec![0x4, 0x3, 0x7, 0x33, 0x37, 0x3f, 0x70f, 0x63f]);
+        // a bit for each channel
+        for (i, mask) in masks.iter().enumerate() {
+            assert_eq!(mask.count_ones() as usize, i + 1);
+        }
+    }
+
+    #[test]
+    fn test_stream_info_invalid_bit_depth() {
+        for &bits_per_sample in &[1, 2, 3] {
+            let err = read_stream_info(&stream_info_bytes(bits_per_sample)).unwrap_err();
+            match err.code() {
+                ErrorCode::InvalidBitDepth => (),
+                code => panic!("unexpected error {:?}", code)
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_info_invalid() {
+        let expect_invalid = |f: &dyn Fn(&mut Vec<u8>)| {
+            let mut bytes = stream_info_bytes(16);
+            f(&mut bytes);
+            match read_stream_info(&bytes).unwrap_err().code() {
+                ErrorCode::InvalidStreamInfo => (),
+                code => panic!("unexpected error {:?}", code)
+            }
+        };
+        // the minimum block size of 8192 above the maximum of 4096
+        expect_invalid(&|x| x[0] = 0x20);
+        // the sample rate of 0
+        expect_invalid(&|x| {
+            x[10] = 0;
+            x[11] = 0;
+            x[12] &= 0x0f;
+        });
+        // the minimum frame size of 256 above the maximum of 16
+        expect_invalid(&|x| {
+            x[5] = 0x01;
+            x[9] = 0x10;
+        });
+        // either frame size unknown
+        let mut bytes = stream_info_bytes(16);
+        bytes[5] = 0x01;
+        assert!(read_stream_info(&bytes).is_ok());
+        // fields assigned after reading are validated as well
+        let mut info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        info.number_of_channels = 9;
+        assert!(info.validate().is_err());
+        info.number_of_channels = 8;
+        info.bits_per_sample = 33;
+        assert!(info.validate().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let mut info = read_stream_info(&stream_info_bytes(16)).unwrap();
+        info.signature = 0x0123_4567_89ab_cdef;
+        let json = serde_json::to_value(info).unwrap();
+        assert_eq!(json["sample_rate"], 44100);
+        assert_eq!(json["bits_per_sample"], 16);
+        assert_eq!(json["signature"], "00000000000000000123456789abcdef");
+        let header = MetadataHeader { last: true, metadata_type: MetadataType::VorbisComment, length_in_bytes: 40 };
+        assert_eq!(serde_json::to_string(&header).unwrap(),
+                   r#"{"last":true,"metadata_type":"VorbisComment","length_in_bytes":40}"#);
+        let comment = VorbisComment { vendor: "suono".to_string(), comments: vec![("TITLE".to_string(), "x".to_string())] };
+        assert_eq!(serde_json::to_string(&comment).unwrap(), r#"{"vendor":"suono","comments":[["TITLE","x"]]}"#);
+    }
+
+    #[test]
+    fn test_seek_table() {
+        let mut bytes: &[u8] = &[0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0x02, 0x1c, 0x10, 0,
+                                 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let table = SeekTable::from_reader(&mut reader, 36).unwrap();
+        assert_eq!(table.points, vec![
+            SeekPoint { sample_number: 4096, stream_offset: 540, frame_samples: 4096 },
+            SeekPoint::placeholder()
+        ]);
+        assert!(!table.points[0].is_placeholder());
+        assert!(table.points[1].is_placeholder());
+    }
 }
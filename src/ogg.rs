@@ -0,0 +1,238 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use super::io::{self, Read};
+use super::crc::{Hasher, HasherCrc32Ogg};
+
+// demuxes the FLAC logical bitstream out of an Ogg physical bitstream, reading through
+// its packets as if they were contiguous. the first packet begins with the mapping header
+// followed by the native stream, which is what `Stream::from_ogg` expects.
+pub struct OggReader<R: Read> {
+    underlying: R,
+    // serial number of the FLAC logical bitstream once found
+    serial: Option<u32>,
+    body: Vec<u8>,
+    position: usize,
+    ended: bool
+}
+
+impl<R: Read> OggReader<R> {
+    pub fn new(underlying: R) -> Self {
+        OggReader {
+            underlying,
+            serial: None,
+            body: Vec::new(),
+            position: 0,
+            ended: false
+        }
+    }
+
+    // reads pages until the next one of the FLAC logical bitstream, returning false at the end
+    fn read_page(&mut self) -> io::Result<bool> {
+        loop {
+            let mut header = [0u8; 27];
+            let n = read_fully(&mut self.underlying, &mut header)?;
+            if n == 0 {
+                return Ok(false)
+            }
+            if n < header.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated Ogg page header"))
+            }
+            if &header[0..4] != b"OggS" || header[4] != 0 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid Ogg page"))
+            }
+            let header_type = header[5];
+            let serial = u32::from_le_bytes([header[14], header[15], header[16], header[17]]);
+            let crc32 = u32::from_le_bytes([header[22], header[23], header[24], header[25]]);
+            let mut segments: Vec<u8> = vec![0; header[26] as usize];
+            self.underlying.read_exact(&mut segments)?;
+            let body_len = segments.iter().map(|&x| x as usize).sum();
+            let mut body: Vec<u8> = vec![0; body_len];
+            self.underlying.read_exact(&mut body)?;
+            // the checksum is computed with its own field zeroed
+            header[22..26].fill(0);
+            let mut hasher = HasherCrc32Ogg::new();
+            hasher.hash(&header);
+            hasher.hash(&segments);
+            hasher.hash(&body);
+            if hasher.state() != crc32 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Ogg page CRC mismatch"))
+            }
+            match self.serial {
+                // the FLAC logical bitstream begins with the mapping header
+                None if header_type & 0x02 != 0 && body.starts_with(b"\x7fFLAC") => {
+                    self.serial = Some(serial);
+                },
+                Some(x) if x == serial => (),
+                // pages of other logical bitstreams
+                _ => continue
+            }
+            self.ended = header_type & 0x04 != 0;
+            self.body = body;
+            self.position = 0;
+            return Ok(true)
+        }
+    }
+}
+
+impl<R: Read> Read for OggReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+        loop {
+            let remaining = &self.body[self.position..];
+            if !remaining.is_empty() {
+                let n = remaining.len().min(buf.len());
+                buf[..n].copy_from_slice(&remaining[..n]);
+                self.position += n;
+                return Ok(n)
+            }
+            if self.ended || !self.read_page()? {
+                return Ok(0)
+            }
+        }
+    }
+}
+
+// reads until the buffer is filled or the end of stream, returning the number of bytes read
+fn read_fully(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n: usize = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(m) => n += m,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::bits::BitReader;
+    use super::super::decode::DecodingReadProxy;
+    use super::super::error::{ErrorCode, Result};
+    use super::super::stream::Stream;
+
+    static STEREO16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+
+    // the mapping header with STREAMINFO, then each frame as a packet
+    fn stereo16_packets() -> Vec<Vec<u8>> {
+        let mut header = vec![0x7f, b'F', b'L', b'A', b'C', 1, 0, 0, 0];
+        header.extend_from_slice(&STEREO16[..42]);
+        let mut packets = vec![header];
+        let offsets = [42, 183, 382, 597, 764, 909];
+        for range in offsets.windows(2) {
+            packets.push(STEREO16[range[0]..range[1]].to_vec());
+        }
+        packets
+    }
+
+    // muxes the packets into pages of at most `max_segments` lacing values each
+    fn mux(serial: u32, packets: &[Vec<u8>], max_segments: usize) -> Vec<Vec<u8>> {
+        let mut segments: Vec<&[u8]> = Vec::new();
+        for packet in packets {
+            let mut chunks: Vec<&[u8]> = packet.chunks(255).collect();
+            // a packet ends with a segment shorter than 255
+            if packet.len() % 255 == 0 {
+                chunks.push(&[]);
+            }
+            segments.extend(chunks);
+        }
+        let num_pages = segments.len().div_ceil(max_segments);
+        let mut continued = false;
+        let mut pages: Vec<Vec<u8>> = Vec::new();
+        for (sequence, chunk) in segments.chunks(max_segments).enumerate() {
+            let mut header_type: u8 = if continued { 0x01 } else { 0x00 };
+            if sequence == 0 {
+                header_type |= 0x02;
+            }
+            if sequence + 1 == num_pages {
+                header_type |= 0x04;
+            }
+            let mut page: Vec<u8> = b"OggS".to_vec();
+            page.extend_from_slice(&[0, header_type]);
+            page.extend_from_slice(&0u64.to_le_bytes());
+            page.extend_from_slice(&serial.to_le_bytes());
+            page.extend_from_slice(&(sequence as u32).to_le_bytes());
+            page.extend_from_slice(&[0, 0, 0, 0, chunk.len() as u8]);
+            page.extend(chunk.iter().map(|x| x.len() as u8));
+            for segment in chunk {
+                page.extend_from_slice(segment);
+            }
+            let mut hasher = HasherCrc32Ogg::new();
+            hasher.hash(&page);
+            page[22..26].copy_from_slice(&hasher.state().to_le_bytes());
+            continued = chunk.last().is_some_and(|x| x.len() == 255);
+            pages.push(page);
+        }
+        pages
+    }
+
+    fn decode_stereo(bytes: &[u8]) -> Result<(Vec<i16>, Vec<i16>)> {
+        let mut ogg = OggReader::new(bytes);
+        let mut proxy = DecodingReadProxy::new(&mut ogg);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::from_ogg(&mut reader)?;
+        stream.decode_stereo(&mut reader)
+    }
+
+    fn expected() -> (Vec<i16>, Vec<i16>) {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        stream.decode_stereo(&mut reader).unwrap()
+    }
+
+    #[test]
+    fn test_decode_ogg() {
+        // packets sharing a page, and packets spanning pages
+        for &max_segments in &[255, 1, 2] {
+            let bytes = mux(0x1234, &stereo16_packets(), max_segments).concat();
+            assert_eq!(decode_stereo(&bytes).unwrap(), expected());
+        }
+    }
+
+    #[test]
+    fn test_decode_ogg_multiplexed() {
+        // another logical bitstream comes first and interleaves
+        let other = mux(7, &[b"\x01vorbis".to_vec(), vec![0; 300], vec![1; 10]], 1);
+        let flac = mux(8, &stereo16_packets(), 1);
+        let mut bytes: Vec<u8> = Vec::new();
+        for i in 0..flac.len().max(other.len()) {
+            bytes.extend(other.get(i).into_iter().flatten());
+            bytes.extend(flac.get(i).into_iter().flatten());
+        }
+        assert_eq!(decode_stereo(&bytes).unwrap(), expected());
+    }
+
+    #[test]
+    fn test_decode_ogg_corrupted() {
+        let mut bytes = mux(0x1234, &stereo16_packets(), 255).concat();
+        // flip a bit in the body of the last page
+        let len = bytes.len();
+        bytes[len - 10] ^= 0x01;
+        match decode_stereo(&bytes).unwrap_err().code() {
+            ErrorCode::Io(e) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_native_stream_in_ogg() {
+        // the native stream without the mapping header
+        let packets = vec![STEREO16.to_vec()];
+        let mut bytes = mux(0x1234, &packets, 255).concat();
+        // the page is skipped as not FLAC, leaving nothing to read
+        assert!(decode_stereo(&bytes).is_err());
+        // only the major version 1 is defined
+        bytes = mux(0x1234, &[b"\x7fFLAC\x02\x00\x00\x00".to_vec()], 255).concat();
+        match decode_stereo(&bytes).unwrap_err().code() {
+            ErrorCode::UnsupportedStreamFormat => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+
+// configures how strictly a stream is decoded
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeOptions {
+    // rejects streams deviating from the specification instead of decoding them as far as possible,
+    // including metadata blocks of the reserved types
+    pub strict: bool,
+    // compares the MD5 of the decoded samples with the signature of STREAMINFO at the end of stream
+    pub verify_md5: bool,
+    // validates the CRC-8 of frame headers and the CRC-16 of frames, which can be turned off
+    // for trusted sources to save computing them
+    pub verify_crc: bool,
+    // the independent channels to decode as bits, e.g. 0b01 for the left of stereo. the rest
+    // are read past and left silent, saving the restoration of their signals. channels coded
+    // as side stereo depend on each other, so are decoded regardless. None decodes every channel.
+    pub channel_mask: Option<u8>,
+    // skips a corrupt frame to the next frame sync code instead of failing, the same way as
+    // `Stream::decode_frames_lossy` but without reporting the gaps
+    pub resync_on_error: bool,
+    // collects how the residuals of every subframe are coded into `SubframeStats::residual`,
+    // e.g. to analyze the compression, which allocates for each subframe
+    pub inspect_residuals: bool
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            strict: false,
+            verify_md5: false,
+            verify_crc: true,
+            channel_mask: None,
+            resync_on_error: false,
+            inspect_residuals: false
+        }
+    }
+}
+
+impl DecodeOptions {
+    pub fn strict() -> Self {
+        DecodeOptions { strict: true, ..Default::default() }
+    }
+
+    // the options can be chained from the default as a builder, e.g.
+    // `DecodeOptions::default().verify_crc(false).resync_on_error(true)`
+    pub fn verify_crc(self, verify_crc: bool) -> Self {
+        DecodeOptions { verify_crc, ..self }
+    }
+
+    pub fn verify_md5(self, verify_md5: bool) -> Self {
+        DecodeOptions { verify_md5, ..self }
+    }
+
+    pub fn resync_on_error(self, resync_on_error: bool) -> Self {
+        DecodeOptions { resync_on_error, ..self }
+    }
+
+    pub fn inspect_residuals(self, inspect_residuals: bool) -> Self {
+        DecodeOptions { inspect_residuals, ..self }
+    }
+
+    pub fn channel_mask(self, channel_mask: Option<u8>) -> Self {
+        DecodeOptions { channel_mask, ..self }
+    }
+
+    // decodes only the channels of the indices, ignoring those beyond the 8 channels of FLAC
+    pub fn with_channels(self, channels: &[usize]) -> Self {
+        let mask = channels.iter()
+            .filter(|&&x| x < 8)
+            .fold(0u8, |acc, &x| acc | (1 << x));
+        DecodeOptions { channel_mask: Some(mask), ..self }
+    }
+
+    pub fn decodes_channel(&self, channel: usize) -> bool {
+        match self.channel_mask {
+            Some(mask) => channel < 8 && mask & (1 << channel) != 0,
+            None => true
+        }
+    }
+
+    // whether every channel of the count is decoded, e.g. as required by the MD5 signature
+    pub fn decodes_channels(&self, num_channels: usize) -> bool {
+        (0..num_channels).all(|x| self.decodes_channel(x))
+    }
+}
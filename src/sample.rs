@@ -0,0 +1,241 @@
+
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::fmt::Debug;
+
+// a sample type that the decoder writes into directly
+pub trait Sample: Copy + Default + PartialEq + Debug + Send {
+    // a type holding at least one more bit, used for the side channel of stereo frames
+    type Wide: Sample;
+
+    const BITS: usize;
+
+    // truncates to the low `BITS` bits. the decoder relies on the wrapping arithmetic
+    // so that intermediate values such as residuals never need to fit in the type.
+    fn from_i64(v: i64) -> Self;
+    fn to_i64(self) -> i64;
+}
+
+impl Sample for i16 {
+    type Wide = i32;
+
+    const BITS: usize = 16;
+
+    #[inline]
+    fn from_i64(v: i64) -> Self {
+        v as i16
+    }
+
+    #[inline]
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Sample for i32 {
+    type Wide = i64;
+
+    const BITS: usize = 32;
+
+    #[inline]
+    fn from_i64(v: i64) -> Self {
+        v as i32
+    }
+
+    #[inline]
+    fn to_i64(self) -> i64 {
+        self as i64
+    }
+}
+
+impl Sample for i64 {
+    type Wide = i64;
+
+    const BITS: usize = 64;
+
+    #[inline]
+    fn from_i64(v: i64) -> Self {
+        v
+    }
+
+    #[inline]
+    fn to_i64(self) -> i64 {
+        self
+    }
+}
+
+// an output type the decoded samples are converted to, scaling them from their bit depth
+// to the full range of the type. see `Frame::convert`
+pub trait FromSample: Copy {
+    // the bit depth the type holds without loss
+    const BITS: usize;
+
+    // drops the least significant bits of samples deeper than `BITS`
+    fn from_sample(sample: i64, bits: usize) -> Self;
+}
+
+impl FromSample for i16 {
+    const BITS: usize = 16;
+
+    #[inline]
+    fn from_sample(sample: i64, bits: usize) -> Self {
+        if bits >= 16 {
+            (sample >> (bits - 16)) as i16
+        } else {
+            (sample << (16 - bits)) as i16
+        }
+    }
+}
+
+impl FromSample for i32 {
+    const BITS: usize = 32;
+
+    #[inline]
+    fn from_sample(sample: i64, bits: usize) -> Self {
+        if bits >= 32 {
+            (sample >> (bits - 32)) as i32
+        } else {
+            (sample << (32 - bits)) as i32
+        }
+    }
+}
+
+// in the range of [-1.0, 1.0), exact up to the 24 bits of the significand
+impl FromSample for f32 {
+    const BITS: usize = 24;
+
+    #[inline]
+    fn from_sample(sample: i64, bits: usize) -> Self {
+        sample as f32 / ((1u64 << (bits - 1)) as f32)
+    }
+}
+
+// receives interleaved samples left-justified in whole bytes, the way WAV stores
+// bit depths such as 12 and 20 bits. see `Frame::write_to_samples`
+pub trait SampleSink {
+    type Error;
+
+    fn write_sample(&mut self, sample: i32) -> core::result::Result<(), Self::Error>;
+}
+
+impl SampleSink for Vec<i32> {
+    type Error = Infallible;
+
+    fn write_sample(&mut self, sample: i32) -> core::result::Result<(), Self::Error> {
+        self.push(sample);
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", feature = "hound"))]
+impl<W: std::io::Write + std::io::Seek> SampleSink for hound::WavWriter<W> {
+    type Error = hound::Error;
+
+    fn write_sample(&mut self, sample: i32) -> core::result::Result<(), Self::Error> {
+        hound::WavWriter::write_sample(self, sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use super::super::bits::BitReader;
+    use super::super::decode::DecodingReadProxy;
+    use super::super::error::{ErrorCode, Result};
+    use super::super::frame::Frame;
+    use super::super::stream::Stream;
+
+    fn decode<S: Sample>(bytes: &[u8]) -> Result<Vec<Vec<S>>> {
+        let mut bytes: &[u8] = bytes;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader)?;
+        let mut channels: Vec<Vec<S>> = vec![vec![]; stream.stream_info.number_of_channels];
+        stream.decode_frames(&mut reader, |frame: &Frame<S>| {
+            for (channel, block) in channels.iter_mut().zip(frame.blocks.iter()) {
+                channel.extend_from_slice(block);
+            }
+        })?;
+        Ok(channels)
+    }
+
+    fn widen<S: Sample>(channels: Vec<Vec<S>>) -> Vec<Vec<i64>> {
+        channels.into_iter()
+            .map(|x| x.into_iter().map(|v| v.to_i64()).collect())
+            .collect()
+    }
+
+    #[cfg(feature = "hound")]
+    #[test]
+    fn test_wav_sample_sink() {
+        let spec = hound::WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 24, sample_format: hound::SampleFormat::Int };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        {
+            let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+            // the largest and the smallest 20-bit samples left-justified in 24 bits
+            SampleSink::write_sample(&mut writer, ((1 << 19) - 1) << 4).unwrap();
+            SampleSink::write_sample(&mut writer, -(1 << 19) << 4).unwrap();
+            writer.finalize().unwrap();
+        }
+        cursor.set_position(0);
+        let mut reader = hound::WavReader::new(cursor).unwrap();
+        let samples: Vec<i32> = reader.samples::<i32>().map(|x| x.unwrap()).collect();
+        assert_eq!(samples, vec![0x7ffff0, -0x800000]);
+    }
+
+    #[test]
+    fn test_wrapping() {
+        assert_eq!(i16::from_i64(0x1_8000), -0x8000);
+        assert_eq!(i16::from_i64(-1).to_i64(), -1);
+        assert_eq!(i32::from_i64(0x1_0000_0001), 1);
+        assert_eq!(i64::from_i64(i64::MIN), i64::MIN);
+    }
+
+    #[test]
+    fn test_from_sample() {
+        // scaled up from fewer bits
+        assert_eq!(i16::from_sample(-128, 8), -0x8000);
+        assert_eq!(i32::from_sample(0x7fff, 16), 0x7fff_0000);
+        // the least significant bits dropped
+        assert_eq!(i16::from_sample(0x7f_ffff, 24), 0x7fff);
+        assert_eq!(i16::from_sample(-1, 24), -1);
+        assert_eq!(i16::from_sample(0x12_3456, 24), 0x1234);
+        assert_eq!(i32::from_sample(i32::MIN as i64, 32), i32::MIN);
+        assert_eq!(f32::from_sample(-0x80_0000, 24), -1.0);
+        assert_eq!(f32::from_sample(0x4000, 16), 0.5);
+    }
+
+    #[test]
+    fn test_decode_16bit() {
+        let bytes = include_bytes!("../testdata/stereo16.flac");
+        let samples = widen(decode::<i32>(bytes).unwrap());
+        assert_eq!((samples[0][0], samples[1][0]), (37, 0));
+        assert_eq!((samples[0][129], samples[1][129]), (1775, 5549));
+        assert_eq!(widen(decode::<i16>(bytes).unwrap()), samples);
+        assert_eq!(widen(decode::<i64>(bytes).unwrap()), samples);
+    }
+
+    #[test]
+    fn test_decode_24bit() {
+        let bytes = include_bytes!("../testdata/stereo24.flac");
+        let samples = widen(decode::<i32>(bytes).unwrap());
+        assert_eq!((samples[0][197], samples[1][197]), (8388607, -8388608));
+        assert_eq!((samples[0][198], samples[1][198]), (-8388608, 8388607));
+        assert_eq!(widen(decode::<i64>(bytes).unwrap()), samples);
+        match decode::<i16>(bytes).unwrap_err().code() {
+            ErrorCode::SampleTypeTooNarrow => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_decode_32bit() {
+        let bytes = include_bytes!("../testdata/stereo32.flac");
+        let samples = widen(decode::<i64>(bytes).unwrap());
+        assert_eq!((samples[0][197], samples[1][197]), (2147483647, -2147483648));
+        assert_eq!((samples[0][198], samples[1][198]), (-2147483648, 2147483647));
+        assert_eq!((samples[0][231], samples[1][231]), (-1346017789, -153186995));
+        assert_eq!(widen(decode::<i32>(bytes).unwrap()), samples);
+    }
+}
@@ -0,0 +1,376 @@
+use core::convert::TryInto;
+use super::io;
+use super::io::Result;
+use super::bits::BitRead;
+use super::bitvec::Bitvec;
+use super::crc::{Hasher, HasherCrc8, HasherCrc16Buypass};
+use super::decode::{self, Decode, DecodingRead};
+use super::error;
+
+// reads bits directly from a slice holding the whole stream in memory, in place of
+// `BitReader` over `DecodingReadProxy` which copies every read through the source.
+// the CRCs are computed over the bytes read at once when they are ended.
+pub struct SliceBitReader<'a> {
+    bytes: &'a [u8],
+    // in bits from the beginning of the slice
+    position: usize,
+    byte_offset: u64,
+    // the bytes read when the computation of each CRC began
+    crc8_start: Option<usize>,
+    crc16_start: Option<usize>
+}
+
+impl<'a> SliceBitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        SliceBitReader::with_byte_offset(bytes, 0)
+    }
+
+    // counts from the offset, for a slice beginning midway in the stream
+    pub fn with_byte_offset(bytes: &'a [u8], byte_offset: u64) -> Self {
+        SliceBitReader {
+            bytes,
+            position: 0,
+            byte_offset,
+            crc8_start: None,
+            crc16_start: None
+        }
+    }
+
+    // the bytes not read yet, including the current byte partially read
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position >> 3..]
+    }
+
+    // bytes partially read are counted as read, the same as `BitReader`
+    fn bytes_read(&self) -> usize {
+        (self.position + 7) >> 3
+    }
+
+    fn bits_left(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    // the 8 bytes from the index, padded with zeros past the end
+    #[inline]
+    fn load(&self, index: usize) -> u64 {
+        match self.bytes.get(index..index + 8) {
+            Some(bytes) => u64::from_be_bytes(bytes.try_into().unwrap()),
+            None => {
+                let mut array: [u8; 8] = [0u8; 8];
+                let tail = &self.bytes[index.min(self.bytes.len())..];
+                array[..tail.len()].copy_from_slice(tail);
+                u64::from_be_bytes(array)
+            }
+        }
+    }
+
+    #[inline]
+    fn read_value(&mut self, n: usize) -> Result<u64> {
+        assert!(n <= 64);
+        if n == 0 {
+            return Ok(0)
+        }
+        if n > self.bits_left() {
+            return Err(unexpected_eof())
+        }
+        let index = self.position >> 3;
+        let shift = self.position & 7;
+        let mut value = self.load(index) << shift;
+        // the bits spill over a 9th byte
+        if shift + n > 64 {
+            value |= (self.bytes[index + 8] as u64) >> (8 - shift);
+        }
+        self.position += n;
+        Ok(value >> (64 - n))
+    }
+}
+
+impl<'a> BitRead for SliceBitReader<'a> {
+    fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read_value(1)? == 1)
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_value(8)? as u8)
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        Ok(self.read_value(16)? as u16)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(self.read_value(32)? as u32)
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        self.read_value(64)
+    }
+
+    fn read_u128(&mut self) -> Result<u128> {
+        let mut value: u128 = 0;
+        value |= (self.read_value(64)? as u128) << 64;
+        value |= self.read_value(64)? as u128;
+        Ok(value)
+    }
+
+    fn read_u8_bits(&mut self, n: usize) -> Result<u8> {
+        assert!(n <= 8);
+        Ok(self.read_value(n)? as u8)
+    }
+
+    fn read_u16_bits(&mut self, n: usize) -> Result<u16> {
+        assert!(n <= 16);
+        Ok(self.read_value(n)? as u16)
+    }
+
+    fn read_u32_bits(&mut self, n: usize) -> Result<u32> {
+        assert!(n <= 32);
+        Ok(self.read_value(n)? as u32)
+    }
+
+    fn read_u64_bits(&mut self, n: usize) -> Result<u64> {
+        self.read_value(n)
+    }
+
+    fn read_bitvec(&mut self, vec: &mut Bitvec, mut n: usize) -> Result<()> {
+        if n > self.bits_left() {
+            return Err(unexpected_eof())
+        }
+        // whole bytes at once while aligned
+        if self.is_byte_aligned() {
+            let mut bytes = self.remaining();
+            vec.write_bytes(&mut bytes, n >> 3)?;
+            self.position += n & !7;
+            n &= 7;
+        }
+        while n > 0 {
+            let len = n.min(8);
+            let value = self.read_value(len)? as u8;
+            vec.write_bits(value, len);
+            n -= len;
+        }
+        Ok(())
+    }
+
+    fn skip_bits(&mut self, n: usize) -> Result<()> {
+        if n > self.bits_left() {
+            return Err(unexpected_eof())
+        }
+        self.position += n;
+        Ok(())
+    }
+
+    fn align_to_byte(&mut self) {
+        self.position = self.bytes_read() << 3;
+    }
+
+    fn is_byte_aligned(&self) -> bool {
+        self.bits_into_byte() == 0
+    }
+
+    fn bits_into_byte(&self) -> usize {
+        self.position & 7
+    }
+
+    // counts the 0 bits up to 64 at a time
+    fn read_unary_bounded(&mut self, limit: u32) -> Result<Option<u32>> {
+        let mut n: u32 = 0;
+        loop {
+            let shift = self.position & 7;
+            let v = self.load(self.position >> 3) << shift;
+            let available = 64 - shift;
+            if v != 0 {
+                let zeros = v.leading_zeros() as usize;
+                // the padding past the end has no 1 bit
+                self.position += zeros + 1;
+                return Ok(n.checked_add(zeros as u32).filter(|&x| x <= limit))
+            }
+            if available >= self.bits_left() {
+                self.position += self.bits_left();
+                return Err(unexpected_eof())
+            }
+            self.position += available;
+            // stops reading the run once past the limit
+            n = match n.checked_add(available as u32) {
+                Some(n) if n <= limit => n,
+                _ => return Ok(None)
+            };
+        }
+    }
+}
+
+impl<'a> DecodingRead for SliceBitReader<'a> {
+    fn compute_crc8_begin(&mut self) {
+        self.crc8_start = Some(self.bytes_read());
+    }
+
+    fn compute_crc8_end(&mut self) -> u8 {
+        let mut crc8 = HasherCrc8::new();
+        if let Some(start) = self.crc8_start.take() {
+            crc8.hash(&self.bytes[start..self.bytes_read()]);
+        }
+        crc8.state()
+    }
+
+    fn compute_crc16_begin(&mut self) {
+        self.crc16_start = Some(self.bytes_read());
+    }
+
+    fn compute_crc16_end(&mut self) -> u16 {
+        let mut crc16 = HasherCrc16Buypass::new();
+        if let Some(start) = self.crc16_start.take() {
+            crc16.hash(&self.bytes[start..self.bytes_read()]);
+        }
+        crc16.state()
+    }
+
+    fn byte_offset(&self) -> u64 {
+        self.byte_offset + self.bytes_read() as u64
+    }
+
+    // NOTE: the reader must be aligned to byte
+    fn peek_u8(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.bytes.get(self.bytes_read()).copied())
+    }
+
+    // NOTE: the reader must be aligned to byte
+    fn peek_u16(&mut self) -> io::Result<Option<u16>> {
+        let index = self.bytes_read();
+        Ok(self.bytes.get(index..index + 2).map(|x| u16::from_be_bytes([x[0], x[1]])))
+    }
+}
+
+impl<'a> Decode for SliceBitReader<'a> {
+    fn decode_rice(&mut self, parameter: usize) -> error::Result<i32> {
+        let msb = self.read_unary_bounded(decode::rice_quotient_limit(parameter))?
+            .ok_or_else(|| error::Error::from_code(error::ErrorCode::RiceUnaryTooLong))?;
+        let lsb = self.read_value(parameter)? as u32;
+        decode::fold_rice(msb, lsb, parameter)
+    }
+
+    // takes each code from a single load as long as it fits in the 64 bits
+    fn decode_rice_block(&mut self, parameter: usize, out: &mut [i32]) -> error::Result<()> {
+        let total = self.bytes.len() * 8;
+        for residual in out.iter_mut() {
+            let shift = self.position & 7;
+            let v = self.load(self.position >> 3) << shift;
+            let zeros = v.leading_zeros() as usize;
+            let len = zeros + 1 + parameter;
+            if len > 64 - shift || self.position + len > total {
+                *residual = self.decode_rice(parameter)?;
+                continue;
+            }
+            let lsb = match parameter {
+                0 => 0,
+                _ => ((v << (zeros + 1)) >> (64 - parameter)) as u32
+            };
+            self.position += len;
+            *residual = decode::fold_rice(zeros as u32, lsb, parameter)?;
+        }
+        Ok(())
+    }
+}
+
+fn unexpected_eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use super::super::bits::BitReader;
+    use super::super::decode::DecodingReadProxy;
+    use super::super::frame::Frame;
+    use super::super::stream::Stream;
+
+    #[test]
+    fn test_read_bits() {
+        let bytes: &[u8] = &[0b10110110, 0b11001100, 0b11110110, 0b11001001,
+                             0b10001001, 0b11101101, 0b01001000, 0b01011001, 0b01011001];
+        let mut reader = SliceBitReader::new(bytes);
+        assert_eq!(reader.read_u8_bits(3).unwrap(), 0b101);
+        // across the 9th byte
+        assert_eq!(reader.read_u64_bits(64).unwrap(),
+                   0b1011011001100111101101100100110001001111011010100100001011001010);
+        assert_eq!(reader.bits_into_byte(), 3);
+        assert_eq!(reader.byte_offset(), 9);
+        assert_eq!(reader.read_u8_bits(5).unwrap(), 0b11001);
+        assert!(reader.read_bool().is_err());
+    }
+
+    #[test]
+    fn test_read_unary() {
+        let mut bytes: Vec<u8> = vec![0b0001_0000, 0, 0, 0, 0, 0, 0, 0, 0, 0b0000_0100];
+        let mut reader = SliceBitReader::new(&bytes);
+        assert_eq!(reader.read_unary().unwrap(), 3);
+        assert_eq!(reader.read_unary().unwrap(), 4 + 64 + 5);
+        assert_eq!(reader.read_u8_bits(2).unwrap(), 0);
+        // no 1 bit up to the end
+        bytes[9] = 0;
+        let mut reader = SliceBitReader::new(&bytes);
+        assert_eq!(reader.read_unary().unwrap(), 3);
+        assert_eq!(reader.read_unary().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_rice_unary_too_long() {
+        let bytes = vec![0u8; 1 << 22];
+        let mut reader = SliceBitReader::new(&bytes);
+        assert!(matches!(reader.decode_rice(0).unwrap_err().code(), error::ErrorCode::RiceUnaryTooLong));
+        // stops within the run
+        assert!(reader.remaining().len() >= (1 << 22) - (1 << 21) - 8);
+        let mut reader = SliceBitReader::new(&bytes);
+        let mut residuals = [0i32; 16];
+        let error = reader.decode_rice_block(20, &mut residuals).unwrap_err();
+        assert!(matches!(error.code(), error::ErrorCode::RiceUnaryTooLong));
+        assert!(reader.remaining().len() >= (1 << 22) - 520);
+    }
+
+    #[test]
+    fn test_peek_and_align() {
+        let mut reader = SliceBitReader::with_byte_offset(&[0xff, 0xf8, 0x12], 42);
+        assert_eq!(reader.peek_u16().unwrap(), Some(0xfff8));
+        assert_eq!(reader.read_u16_bits(14).unwrap(), 0x3ffe);
+        assert_eq!(reader.byte_offset(), 44);
+        reader.align_to_byte();
+        assert_eq!(reader.peek_u8().unwrap(), Some(0x12));
+        assert_eq!(reader.peek_u16().unwrap(), None);
+        assert_eq!(reader.remaining(), &[0x12]);
+    }
+
+    #[test]
+    fn test_same_as_proxy() {
+        let files: [&[u8]; 3] = [
+            include_bytes!("../testdata/stereo16.flac"),
+            include_bytes!("../testdata/stereo24.flac"),
+            include_bytes!("../testdata/stereo32.flac")
+        ];
+        for &file in files.iter() {
+            let mut bytes: &[u8] = file;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            let mut expected: Vec<(u64, u16, Vec<Vec<i64>>)> = Vec::new();
+            stream.decode_frames(&mut reader, |frame: &Frame<i64>| {
+                expected.push((frame.stats.byte_offset, frame.footer.crc16, frame.blocks.iter().map(|x| x.to_vec()).collect()));
+            }).unwrap();
+            let mut reader = SliceBitReader::new(file);
+            let stream = Stream::new(&mut reader).unwrap();
+            let mut frames: Vec<(u64, u16, Vec<Vec<i64>>)> = Vec::new();
+            stream.decode_frames(&mut reader, |frame: &Frame<i64>| {
+                frames.push((frame.stats.byte_offset, frame.footer.crc16, frame.blocks.iter().map(|x| x.to_vec()).collect()));
+            }).unwrap();
+            assert_eq!(frames, expected);
+            stream.verify(&mut SliceBitReader::with_byte_offset(&file[stream.first_frame_offset() as usize..], stream.first_frame_offset())).unwrap();
+        }
+        // corruption is caught by the CRC the same way
+        let mut data = include_bytes!("../testdata/stereo16.flac").to_vec();
+        data[183 + 50] ^= 0x01;
+        let mut reader = SliceBitReader::new(&data);
+        let stream = Stream::new(&mut reader).unwrap();
+        let error = stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap_err();
+        assert!(matches!(error.code(), error::ErrorCode::FrameCrcMismatch));
+    }
+}
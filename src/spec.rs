@@ -0,0 +1,87 @@
+use super::metadata::StreamInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleFormat {
+    Int,
+    Float
+}
+
+// describes the decoded PCM for passing to audio APIs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioSpec {
+    pub sample_rate: usize,
+    pub channels: usize,
+    pub bits_per_sample: usize,
+    pub sample_format: SampleFormat
+}
+
+impl StreamInfo {
+    // FLAC always decodes to integer samples
+    pub fn spec(&self) -> AudioSpec {
+        AudioSpec {
+            sample_rate: self.sample_rate,
+            channels: self.number_of_channels,
+            bits_per_sample: self.bits_per_sample,
+            sample_format: SampleFormat::Int
+        }
+    }
+}
+
+// depths such as 20 bits are rounded up to whole bytes, which hound only writes.
+// the samples are to be left-justified accordingly, see `Frame::write_to_samples`
+#[cfg(feature = "hound")]
+impl From<AudioSpec> for hound::WavSpec {
+    fn from(spec: AudioSpec) -> Self {
+        hound::WavSpec {
+            channels: spec.channels as u16,
+            sample_rate: spec.sample_rate as u32,
+            bits_per_sample: (spec.bits_per_sample.div_ceil(8) * 8) as u16,
+            sample_format: match spec.sample_format {
+                SampleFormat::Int => hound::SampleFormat::Int,
+                SampleFormat::Float => hound::SampleFormat::Float
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::bits::BitReader;
+    use super::super::decode::DecodingReadProxy;
+    use super::super::stream::Stream;
+
+    fn read_stream_info() -> StreamInfo {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo24.flac");
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        Stream::new(&mut reader).unwrap().stream_info
+    }
+
+    #[test]
+    fn test_spec() {
+        let spec = read_stream_info().spec();
+        assert_eq!(spec, AudioSpec {
+            sample_rate: 96000,
+            channels: 2,
+            bits_per_sample: 24,
+            sample_format: SampleFormat::Int
+        });
+    }
+
+    #[cfg(feature = "hound")]
+    #[test]
+    fn test_wav_spec() {
+        let spec: hound::WavSpec = read_stream_info().spec().into();
+        assert_eq!(spec, hound::WavSpec {
+            channels: 2,
+            sample_rate: 96000,
+            bits_per_sample: 24,
+            sample_format: hound::SampleFormat::Int
+        });
+        let spec: hound::WavSpec = AudioSpec { bits_per_sample: 20, ..read_stream_info().spec() }.into();
+        assert_eq!(spec.bits_per_sample, 24);
+        let spec: hound::WavSpec = AudioSpec { bits_per_sample: 12, ..read_stream_info().spec() }.into();
+        assert_eq!(spec.bits_per_sample, 16);
+    }
+}
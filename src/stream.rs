@@ -1,49 +1,1590 @@
 
 use super::error::{Error, ErrorCode, Result};
 use super::decode::Decode;
-use super::metadata::{MetadataHeader, StreamInfo};
-use super::frame::{Frame};
+#[cfg(feature = "std")]
+use super::decode::DecodingReadProxy;
+#[cfg(feature = "std")]
+use super::bits::BitReader;
+use super::slice::SliceBitReader;
+use super::metadata::{self, Metadata, MetadataBlock, MetadataHeader, MetadataType, StreamInfo, SeekPoint, SeekTable, VorbisComment, Picture, Application};
+use super::frame::{Frame, FrameBuffer, FrameScratch, FrameHeader, FrameSink, FrameSizeSummary, BlockingStrategy};
+use super::iter::FrameIter;
+use super::sample::Sample;
+use super::crc::{Hasher, HasherMd5};
+use super::options::DecodeOptions;
+use super::io;
+#[cfg(feature = "std")]
+use super::downmix::{self, DownmixCoefficients};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom};
+
+// adapts a sink of `decode_frames` to `FrameSink`, never failing but for decoding
+fn infallible<S: Sample, F: FnMut(&Frame<S>)>(mut sink: F) -> impl FnMut(&Frame<S>) -> Result<()> {
+    move |frame| {
+        sink(frame);
+        Ok(())
+    }
+}
+
+// the bytes skipped to resynchronize after a frame failed to decode
+#[derive(Debug)]
+pub struct FrameGap {
+    // offset of the frame failing to decode from the beginning of the stream
+    pub byte_offset: u64,
+    // up to the next frame sync code, or the end of stream
+    pub byte_len: u64,
+    pub error: Error
+}
 
 pub struct Stream {
-    pub stream_info: StreamInfo
+    pub stream_info: StreamInfo,
+    pub vorbis_comment: Option<VorbisComment>,
+    pub seek_table: Option<SeekTable>,
+    pub pictures: Vec<Picture>,
+    pub applications: Vec<Application>,
+    pub options: DecodeOptions,
+    metadata_blocks: Vec<MetadataBlock>,
+    first_frame_offset: u64
 }
 
 impl Stream {
-    pub fn new(reader: &mut Decode) -> Result<Self> {
-        let magic = reader.read_u32()?;
-        if magic != 0x664c6143 {
-            return Err(Error::from_code(ErrorCode::WrongMagic))
-        }
+    pub fn new(reader: &mut dyn Decode) -> Result<Self> {
+        Stream::with_options(reader, DecodeOptions::default())
+    }
+
+    pub fn with_options(reader: &mut dyn Decode, options: DecodeOptions) -> Result<Self> {
+        metadata::read_magic(reader)?;
         let header = MetadataHeader::from_reader(reader)?;
         let stream_info = StreamInfo::from_reader(reader)?;
+        let mut metadata_blocks: Vec<MetadataBlock> = Vec::new();
         if !header.last {
             loop {
                 let header = MetadataHeader::from_reader(reader)?;
-                header.skip_body(reader)?;
-                if header.last {
+                // the types reserved for future use are skipped unless decoding strictly
+                if options.strict && header.metadata_type == MetadataType::Reserved {
+                    return Err(Error::from_code(ErrorCode::InvalidMetadataType))
+                }
+                let last = header.last;
+                metadata_blocks.push(MetadataBlock::from_reader(reader, header)?);
+                if last {
+                    break;
+                }
+            }
+        }
+        // some muxers emit blocks after the last one, which are skipped
+        // until the frame sync unless decoding strictly
+        if !options.strict {
+            while let Some(byte) = reader.peek_u8()? {
+                if byte == 0xff {
                     break;
                 }
+                let header = MetadataHeader::from_reader(reader)?;
+                MetadataBlock::from_reader(reader, header)?;
+            }
+        }
+        let first_frame_offset = reader.byte_offset();
+        let vorbis_comment = find_metadata(&metadata_blocks, MetadataType::VorbisComment, &options, |x| match x {
+            Metadata::VorbisComment(x) => Some(x),
+            _ => None
+        })?;
+        let seek_table = find_metadata(&metadata_blocks, MetadataType::Seektable, &options, |x| match x {
+            Metadata::SeekTable(x) => Some(x),
+            _ => None
+        })?;
+        let pictures = collect_metadata(&metadata_blocks, MetadataType::Picture, &options, |x| match x {
+            Metadata::Picture(x) => Some(x),
+            _ => None
+        })?;
+        let applications = collect_metadata(&metadata_blocks, MetadataType::Application, &options, |x| match x {
+            Metadata::Application(x) => Some(x),
+            _ => None
+        })?;
+        Ok(Stream { stream_info, vorbis_comment, seek_table, pictures, applications, options, metadata_blocks, first_frame_offset })
+    }
+
+    // reads a stream encapsulated in Ogg, where the reader reads through an `OggReader`
+    pub fn from_ogg(reader: &mut dyn Decode) -> Result<Self> {
+        Stream::from_ogg_with_options(reader, DecodeOptions::default())
+    }
+
+    pub fn from_ogg_with_options(reader: &mut dyn Decode, options: DecodeOptions) -> Result<Self> {
+        // the mapping header precedes the native stream in the first packet
+        let packet_type = reader.read_u8()?;
+        let magic = reader.read_u32()?;
+        if packet_type != 0x7f || magic != 0x464c4143 {
+            return Err(Error::from_code(ErrorCode::WrongMagic))
+        }
+        let major_version = reader.read_u8()?;
+        let _minor_version = reader.read_u8()?;
+        // the number of header packets following, which are the metadata blocks
+        let _num_header_packets = reader.read_u16()?;
+        if major_version != 1 {
+            return Err(Error::from_code(ErrorCode::UnsupportedStreamFormat))
+        }
+        Stream::with_options(reader, options)
+    }
+
+    // offset in bytes of the first frame header from the beginning of the stream
+    pub fn first_frame_offset(&self) -> u64 {
+        self.first_frame_offset
+    }
+
+    // calls back with the header and raw body of every metadata block including STREAMINFO and padding,
+    // reading from the beginning of the stream. the reader is left at the first frame.
+    pub fn for_each_metadata<F>(reader: &mut dyn Decode, mut callback: F) -> Result<()>
+        where F: FnMut(&MetadataHeader, &[u8]) {
+        metadata::read_magic(reader)?;
+        loop {
+            let header = MetadataHeader::from_reader(reader)?;
+            let mut body: Vec<u8> = vec![0; header.length_in_bytes];
+            for byte in &mut body[..] {
+                *byte = reader.read_u8()?;
+            }
+            callback(&header, &body);
+            if header.last {
+                break;
             }
         }
-        Ok(Stream { stream_info: stream_info })
+        Ok(())
+    }
+
+    // iterates over every metadata block, parsing each as it is requested
+    pub fn metadata(&self) -> impl Iterator<Item = Result<Metadata>> + '_ {
+        let stream_info = Metadata::StreamInfo(self.stream_info);
+        core::iter::once(Ok(stream_info))
+            .chain(self.metadata_blocks.iter().map(|x| x.parse()))
+    }
+
+    // decodes the samples as `S`, which is inferred from the sink.
+    // returns the sizes of the frames decoded.
+    pub fn decode_frames<S, F>(&self, reader: &mut dyn Decode, sink: F) -> Result<FrameSizeSummary>
+        where S: Sample, F: FnMut(&Frame<S>) {
+        self.decode_frames_until(reader, None, self.options.verify_md5, infallible(sink))
+    }
+
+    // same as `decode_frames`, but the sink is able to fail, aborting decoding with its error.
+    // the error of the sink has to hold the errors of decoding as well, as does e.g.
+    // `Box<dyn std::error::Error>`
+    pub fn try_decode_frames<S, K>(&self, reader: &mut dyn Decode, sink: K) -> core::result::Result<FrameSizeSummary, K::Error>
+        where S: Sample, K: FrameSink<S> {
+        self.decode_frames_until(reader, None, self.options.verify_md5, sink)
+    }
+
+    // same as `decode_frames`, but stops before the next frame once `cancel` is set.
+    // an `Arc<AtomicBool>` shared with another thread can be passed as is.
+    pub fn decode_frames_cancellable<S, F>(&self, reader: &mut dyn Decode, cancel: &AtomicBool, sink: F) -> Result<FrameSizeSummary>
+        where S: Sample, F: FnMut(&Frame<S>) {
+        self.decode_frames_until(reader, Some(cancel), self.options.verify_md5, infallible(sink))
     }
 
-    pub fn decode_frames<F>(&self, reader: &mut Decode, mut sink: F) -> Result<()>
-        where F: FnMut(&Frame) -> () {
+    // same as `decode_frames`, but instead of failing on a corrupt frame, skips to the next
+    // frame sync code and resumes from there, calling back with the gap. the CRC-8 of the
+    // header makes a false sync unlikely. the number of samples lost can be told from the
+    // sample number of the following frame. the MD5 signature is not verified.
+    // NOTE: I/O errors other than the end of stream still fail
+    pub fn decode_frames_lossy<S, F, G>(&self, reader: &mut dyn Decode, mut sink: F, mut on_gap: G) -> Result<FrameSizeSummary>
+        where S: Sample, F: FnMut(&Frame<S>), G: FnMut(FrameGap) {
+        let mut blocks: Vec<FrameBuffer<S>> = self.allocate_blocks();
+        let mut scratch: FrameScratch<S> = self.allocate_scratch();
+        let mut summary = FrameSizeSummary::default();
+        loop {
+            // junk before the frame, found by peeking so as not to miss a sync code within
+            let byte_offset = reader.byte_offset();
+            Stream::skip_to_frame_sync(reader)?;
+            if reader.byte_offset() != byte_offset {
+                on_gap(FrameGap {
+                    byte_offset,
+                    byte_len: reader.byte_offset() - byte_offset,
+                    error: Error::from_code(ErrorCode::FrameOutOfSync)
+                });
+            }
+            let byte_offset = reader.byte_offset();
+            let error = match Frame::from_reader_with_scratch(reader, &self.stream_info, &self.options, &mut blocks, &mut scratch) {
+                Ok(None) => break,
+                Ok(Some(frame)) => {
+                    summary.add(&frame.stats);
+                    sink(&frame);
+                    scratch.recycle(frame.stats);
+                    continue;
+                },
+                Err(e) => e
+            };
+            if !Stream::is_recoverable(&error) {
+                return Err(error)
+            }
+            Stream::skip_to_frame_sync(reader)?;
+            on_gap(FrameGap {
+                byte_offset,
+                byte_len: reader.byte_offset() - byte_offset,
+                error
+            });
+        }
+        Ok(summary)
+    }
+
+    // errors of the sample type or the source rather than of the frame cannot be skipped
+    fn is_recoverable(error: &Error) -> bool {
+        match error.code() {
+            ErrorCode::Io(e) => e.kind() == io::ErrorKind::UnexpectedEof,
+            ErrorCode::SampleTypeTooNarrow | ErrorCode::FrameBufferUnallocated => false,
+            _ => true
+        }
+    }
+
+    // drops bytes up to the next frame sync code, which is left unread
+    fn skip_to_frame_sync(reader: &mut dyn Decode) -> Result<()> {
+        reader.align_to_byte();
+        while let Some(bytes) = reader.peek_u16()? {
+            // the last bit is the blocking strategy
+            if bytes & 0xfffe == 0xfff8 {
+                break;
+            }
+            reader.read_u8()?;
+        }
+        Ok(())
+    }
+
+    fn decode_frames_until<S, K>(&self, reader: &mut dyn Decode, cancel: Option<&AtomicBool>, verify_md5: bool, mut sink: K) -> core::result::Result<FrameSizeSummary, K::Error>
+        where S: Sample, K: FrameSink<S> {
         // allocate buffer in advance
-        let mut blocks: Vec<Vec<i32>> = Vec::new();
+        let mut blocks: Vec<FrameBuffer<S>> = self.allocate_blocks();
+        let mut scratch: FrameScratch<S> = self.allocate_scratch();
+        let mut summary = FrameSizeSummary::default();
+        // a zero signature means it is unknown, and it covers the channels skipped as well
+        let verify_md5 = verify_md5 && self.options.decodes_channels(self.stream_info.number_of_channels);
+        let mut md5 = if verify_md5 && self.stream_info.signature != 0 {
+            Some(HasherMd5::new())
+        } else {
+            None
+        };
+        // the blocking strategy and block size of the previous frame
+        let mut previous: Option<(BlockingStrategy, usize)> = None;
+        loop {
+            if cancel.is_some_and(|x| x.load(Ordering::Relaxed)) {
+                return Ok(summary)
+            }
+            if self.options.resync_on_error {
+                Stream::skip_to_frame_sync(reader)?;
+            }
+            let frame = match Frame::from_reader_with_scratch(reader, &self.stream_info, &self.options, &mut blocks, &mut scratch) {
+                Ok(None) => break,
+                Ok(Some(frame)) => frame,
+                // the frame lost fails the MD5 signature if verified
+                Err(e) if self.options.resync_on_error && Stream::is_recoverable(&e) => {
+                    Stream::skip_to_frame_sync(reader)?;
+                    continue;
+                },
+                Err(e) => return Err(e.into())
+            };
+            if self.options.strict {
+                self.check_block_size(previous, &frame.header)?;
+            }
+            previous = Some((frame.header.blocking_strategy, frame.header.block_size));
+            // the signature covers the whole stream, so it cannot be checked
+            // when decoding begins midway such as after seeking
+            if summary.num_frames == 0 && frame.header.coded_number != 0 {
+                md5 = None;
+            }
+            summary.add(&frame.stats);
+            if let Some(md5) = &mut md5 {
+                frame.update_md5(md5);
+            }
+            sink.write_frame(&frame)?;
+            scratch.recycle(frame.stats);
+        }
+        if md5.is_some_and(|x| x.state() != self.stream_info.signature) {
+            return Err(Error::from_code(ErrorCode::Md5Mismatch).into())
+        }
+        Ok(summary)
+    }
+
+    // every frame of a fixed-blocksize stream but the last has the same block size,
+    // and the strategy does not change within a stream
+    fn check_block_size(&self, previous: Option<(BlockingStrategy, usize)>, header: &FrameHeader) -> Result<()> {
+        let valid = match previous {
+            None => true,
+            Some((BlockingStrategy::Fixed, block_size)) => header.blocking_strategy == BlockingStrategy::Fixed
+                && block_size == self.stream_info.max_block_size,
+            Some((BlockingStrategy::Variable, _)) => header.blocking_strategy == BlockingStrategy::Variable
+        };
+        if !valid {
+            return Err(Error::from_code(ErrorCode::FrameBlockSizeMismatch))
+        }
+        Ok(())
+    }
+
+    // allocates a buffer for each channel large enough for any frame of the stream
+    pub fn allocate_blocks<S: Sample>(&self) -> Vec<FrameBuffer<S>> {
+        let mut blocks: Vec<FrameBuffer<S>> = Vec::new();
         let buffer_capacity = self.stream_info.max_block_size;
-        blocks.resize_with(self.stream_info.number_of_channels, || Vec::with_capacity(buffer_capacity));
+        blocks.resize_with(self.stream_info.number_of_channels, || FrameBuffer::with_capacity(buffer_capacity));
+        blocks
+    }
+
+    // allocates the storage decoding a frame needs besides the blocks, where the side channels
+    // take a buffer of their own only if their extra bit does not fit in `S`
+    pub fn allocate_scratch<S: Sample>(&self) -> FrameScratch<S> {
+        let info = &self.stream_info;
+        let wide_capacity = if info.bits_per_sample + 1 > S::BITS { info.max_block_size } else { 0 };
+        FrameScratch::with_capacity(wide_capacity, info.number_of_channels)
+    }
+
+    // decodes the frame at the current position into the blocks allocated by `allocate_blocks`,
+    // returning its header or None at the end of stream. the reader is left at the next frame.
+    pub fn decode_next_frame<S: Sample>(&self, reader: &mut dyn Decode, blocks: &mut Vec<FrameBuffer<S>>) -> Result<Option<FrameHeader>> {
+        let frame = Frame::from_reader(reader, &self.stream_info, &self.options, blocks)?;
+        Ok(frame.map(|x| x.header))
+    }
+
+    // same as `decode_next_frame`, then copies the samples into the slices of the caller, one per
+    // channel as long as the block size, e.g. sized to the maximum of STREAMINFO up front. with
+    // the blocks and the scratch from `allocate_blocks` and `allocate_scratch` reused, decoding
+    // frames allocates nothing, unless residuals are inspected or channels restored in parallel.
+    // returns the header telling the number of samples written to each slice.
+    pub fn decode_next_frame_into<S: Sample>(&self, reader: &mut dyn Decode, blocks: &mut Vec<FrameBuffer<S>>, scratch: &mut FrameScratch<S>, channels: &mut [&mut [S]]) -> Result<Option<FrameHeader>> {
+        let frame = match Frame::from_reader_with_scratch(reader, &self.stream_info, &self.options, blocks, scratch)? {
+            None => return Ok(None),
+            Some(frame) => frame
+        };
+        let num_channels = frame.header.channel_assignment.num_channels();
+        if channels.len() < num_channels {
+            return Err(Error::from_code(ErrorCode::FrameBufferUnallocated))
+        }
+        for (channel, block) in channels.iter_mut().zip(frame.blocks.iter().take(num_channels)) {
+            channel.get_mut(..block.len())
+                .ok_or_else(|| Error::from_code(ErrorCode::FrameBufferUnallocated))?
+                .copy_from_slice(block);
+        }
+        scratch.recycle(frame.stats);
+        Ok(Some(frame.header))
+    }
+
+    // decodes frames on demand, as an alternative to `decode_frames` which allows stopping early
+    pub fn frames<'r>(&'r self, reader: &'r mut dyn Decode) -> FrameIter<'r> {
+        FrameIter::new(self, reader)
+    }
+
+    // decodes every frame to validate its integrity including the MD5 signature, discarding the samples
+    pub fn verify(&self, reader: &mut dyn Decode) -> Result<()> {
+        self.decode_frames_until(reader, None, true, infallible(|_: &Frame| ()))?;
+        Ok(())
+    }
+
+    // decodes a 16-bit stereo stream into the left and right channels
+    pub fn decode_stereo(&self, reader: &mut dyn Decode) -> Result<(Vec<i16>, Vec<i16>)> {
+        let info = &self.stream_info;
+        if info.number_of_channels != 2 || info.bits_per_sample != 16 {
+            return Err(Error::from_code(ErrorCode::UnsupportedStreamFormat))
+        }
+        let mut left: Vec<i16> = Vec::with_capacity(info.reserved_samples());
+        let mut right: Vec<i16> = Vec::with_capacity(info.reserved_samples());
+        self.decode_frames(reader, |frame: &Frame<i16>| {
+            left.extend_from_slice(&frame.blocks[0]);
+            right.extend_from_slice(&frame.blocks[1]);
+        })?;
+        Ok((left, right))
+    }
+
+    // decodes any number of channels, folding them into the left and right channels
+    #[cfg(feature = "std")]
+    pub fn decode_downmix_stereo(&self, reader: &mut dyn Decode, coefficients: &DownmixCoefficients) -> Result<(Vec<i32>, Vec<i32>)> {
+        let info = &self.stream_info;
+        let gains = coefficients.gains(info.number_of_channels)
+            .ok_or_else(|| Error::from_code(ErrorCode::UnsupportedStreamFormat))?;
+        let mut left: Vec<i32> = Vec::with_capacity(info.reserved_samples());
+        let mut right: Vec<i32> = Vec::with_capacity(info.reserved_samples());
+        self.decode_frames(reader, |frame: &Frame| {
+            downmix::mix(&gains, frame.blocks, frame.header.sample_size, &mut left, &mut right);
+        })?;
+        Ok((left, right))
+    }
+
+    // calls back with windows of `window_size` samples of each channel every `hop` samples,
+    // spanning frame boundaries. the trailing samples not filling a window are discarded.
+    // neither the window nor the hop may be empty.
+    pub fn decode_windows<F>(&self, reader: &mut dyn Decode, window_size: usize, hop: usize, mut callback: F) -> Result<()>
+        where F: FnMut(&[&[i32]]) {
+        if window_size == 0 || hop == 0 {
+            return Err(Error::from_code(ErrorCode::InvalidArgument))
+        }
+        let mut buffers: Vec<Vec<i32>> = Vec::new();
+        let buffer_capacity = window_size + self.stream_info.max_block_size;
+        buffers.resize_with(self.stream_info.number_of_channels, || Vec::with_capacity(buffer_capacity));
+        // beginning of the next window in the buffers
+        let mut position: usize = 0;
+        let num_channels = buffers.len();
+        self.decode_frames(reader, |frame: &Frame| {
+            for (buffer, block) in buffers.iter_mut().zip(frame.blocks.iter()) {
+                buffer.extend_from_slice(block);
+            }
+            let len = buffers[0].len();
+            // FLAC has at most 8 channels, so the windows need not be collected into a Vec
+            let mut windows: [&[i32]; 8] = [&[]; 8];
+            while position + window_size <= len {
+                for (window, buffer) in windows.iter_mut().zip(buffers.iter()) {
+                    *window = &buffer[position..position + window_size];
+                }
+                callback(&windows[..num_channels]);
+                position += hop;
+            }
+            // drop the samples no window needs anymore
+            let consumed = position.min(len);
+            for buffer in &mut buffers {
+                buffer.drain(..consumed);
+            }
+            position -= consumed;
+        })?;
+        Ok(())
+    }
+
+    // replaces the placeholder points of the table with the frames found by scanning the stream.
+    // the reader must be positioned at the first frame.
+    pub fn populate_seek_table(&self, reader: &mut dyn Decode, table: &mut SeekTable) -> Result<()> {
+        let frames = self.scan_frames(reader)?;
+        let total_samples: u64 = frames.last()
+            .map(|x| x.sample_number + (x.frame_samples as u64))
+            .unwrap_or(0);
+        let num_placeholders = table.points.iter()
+            .filter(|x| x.is_placeholder())
+            .count() as u64;
+        // spread the placeholders evenly across the stream
+        let mut targets = (0..num_placeholders)
+            .map(|i| total_samples * i / num_placeholders);
+        for i in 0..table.points.len() {
+            if !table.points[i].is_placeholder() {
+                continue;
+            }
+            let target = match targets.next() {
+                None => break,
+                Some(target) => target
+            };
+            // the last frame starting at or before the target
+            let index = frames.iter()
+                .rposition(|x| x.sample_number <= target);
+            let point = match index {
+                None => continue,
+                Some(index) => frames[index]
+            };
+            // seek points must be unique, leave the placeholder as is otherwise
+            if table.points.iter().any(|x| x.sample_number == point.sample_number) {
+                continue;
+            }
+            table.points[i] = point;
+        }
+        // placeholders are kept at the end of the table
+        table.points.sort_by_key(|x| x.sample_number);
+        Ok(())
+    }
+
+    // moves the reader to the frame at the nearest seek point at or before the target sample,
+    // returning the number of the first sample of that frame so that the caller can discard
+    // the leading samples. without a seek table, frames are scanned from the first one instead.
+    // the reader must be the one underlying the reader the stream was created with.
+    #[cfg(feature = "std")]
+    pub fn seek<R: Read + Seek>(&self, reader: &mut R, target_sample: u64) -> Result<u64> {
+        let point = self.seek_table.as_ref()
+            .and_then(|table| table.points.iter()
+                .filter(|x| !x.is_placeholder() && x.sample_number <= target_sample)
+                .max_by_key(|x| x.sample_number));
+        let (sample_number, stream_offset) = match point {
+            Some(point) => (point.sample_number, point.stream_offset),
+            None => self.scan_to_sample(reader, target_sample)?
+        };
+        reader.seek(SeekFrom::Start(self.first_frame_offset + stream_offset))?;
+        Ok(sample_number)
+    }
+
+    // decodes frames from the first one until the frame containing the target sample,
+    // returning its first sample number and offset from the first frame
+    #[cfg(feature = "std")]
+    fn scan_to_sample<R: Read + Seek>(&self, reader: &mut R, target_sample: u64) -> Result<(u64, u64)> {
+        reader.seek(SeekFrom::Start(self.first_frame_offset))?;
+        let mut proxy = DecodingReadProxy::new(reader);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut blocks: Vec<FrameBuffer> = self.allocate_blocks();
+        let mut sample_number: u64 = 0;
+        let mut found: (u64, u64) = (0, 0);
         loop {
-            let frame = match Frame::from_reader(reader, &self.stream_info, &mut blocks)? {
+            let frame = match Frame::from_reader(&mut reader, &self.stream_info, &self.options, &mut blocks)? {
                 None => break,
                 Some(frame) => frame
             };
-            sink(&frame);
-            for block in &mut blocks[..] {
-                block.clear();
+            // past the end lands on the last frame
+            found = (sample_number, frame.stats.byte_offset);
+            sample_number += frame.header.block_size as u64;
+            if sample_number > target_sample {
+                break;
             }
         }
-        Ok(())
+        Ok(found)
+    }
+
+    // builds an index of every frame, each represented as a seek point
+    fn scan_frames(&self, reader: &mut dyn Decode) -> Result<Vec<SeekPoint>> {
+        let mut blocks: Vec<FrameBuffer> = self.allocate_blocks();
+        let first_frame_offset = reader.byte_offset();
+        let mut sample_number: u64 = 0;
+        let mut frames: Vec<SeekPoint> = Vec::new();
+        loop {
+            let frame = match Frame::from_reader(reader, &self.stream_info, &self.options, &mut blocks)? {
+                None => break,
+                Some(frame) => frame
+            };
+            let frame_samples = frame.header.block_size;
+            frames.push(SeekPoint {
+                sample_number,
+                stream_offset: frame.stats.byte_offset - first_frame_offset,
+                frame_samples: frame_samples as u16
+            });
+            sample_number += frame_samples as u64;
+        }
+        Ok(frames)
+    }
+}
+
+// decodes a whole stream in memory, returning STREAMINFO along with the samples of each channel
+pub fn decode_bytes(bytes: &[u8]) -> Result<(StreamInfo, Vec<Vec<i32>>)> {
+    let mut reader = SliceBitReader::new(bytes);
+    let stream = Stream::new(&mut reader)?;
+    let info = stream.stream_info;
+    let mut channels: Vec<Vec<i32>> = vec![Vec::with_capacity(info.reserved_samples()); info.number_of_channels];
+    stream.decode_frames(&mut reader, |frame: &Frame| {
+        for (channel, block) in channels.iter_mut().zip(frame.blocks.iter()) {
+            channel.extend_from_slice(block);
+        }
+    })?;
+    Ok((info, channels))
+}
+
+// parses every block of the type, skipping malformed ones unless decoding strictly
+fn collect_metadata<T, F>(blocks: &[MetadataBlock], metadata_type: MetadataType, options: &DecodeOptions, extract: F) -> Result<Vec<T>>
+    where F: Fn(Metadata) -> Option<T> {
+    let mut items: Vec<T> = Vec::new();
+    for block in blocks.iter().filter(|x| x.header.metadata_type == metadata_type) {
+        match block.parse() {
+            Ok(metadata) => items.extend(extract(metadata)),
+            Err(_) if !options.strict => continue,
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(items)
+}
+
+fn find_metadata<T, F>(blocks: &[MetadataBlock], metadata_type: MetadataType, options: &DecodeOptions, extract: F) -> Result<Option<T>>
+    where F: Fn(Metadata) -> Option<T> {
+    let items = collect_metadata(blocks, metadata_type, options, extract)?;
+    Ok(items.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::bits::BitReader;
+    use super::super::decode::{DecodingRead, DecodingReadProxy};
+    use super::super::frame::{FrameStats, FrameFooter, PredictionMethod};
+    use super::super::crc::HasherCrc16Buypass;
+    use alloc::string::ToString;
+    #[cfg(feature = "std")]
+    use std::io::Cursor;
+
+    static STEREO16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+    static METADATA: &[u8] = include_bytes!("../testdata/metadata.flac");
+
+    #[test]
+    fn test_verify() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert!(stream.verify(&mut reader).is_ok());
+    }
+
+    #[test]
+    fn test_verify_corrupted() {
+        let mut data = STEREO16.to_vec();
+        // flip a bit inside the last frame's subframes
+        let index = data.len() - 8;
+        data[index] ^= 0x10;
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert!(stream.verify(&mut reader).is_err());
+    }
+
+    // decodes the frames skipping the corrupt ones, returning the sample numbers of the frames
+    // along with the gaps as offsets, lengths and errors
+    fn decode_lossy(data: &[u8]) -> (Vec<u64>, Vec<FrameGap>) {
+        let mut bytes: &[u8] = data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut frames: Vec<u64> = Vec::new();
+        let mut gaps: Vec<FrameGap> = Vec::new();
+        stream.decode_frames_lossy(&mut reader, |frame: &Frame| {
+            frames.push(frame.header.sample_number);
+        }, |gap| gaps.push(gap)).unwrap();
+        (frames, gaps)
+    }
+
+    #[test]
+    fn test_decode_frames_lossy() {
+        let (frames, gaps) = decode_lossy(STEREO16);
+        assert_eq!(frames, vec![0, 64, 128, 192, 256]);
+        assert!(gaps.is_empty());
+        // inside the second frame
+        let mut data = STEREO16.to_vec();
+        data[183 + 50] ^= 0x01;
+        let (frames, gaps) = decode_lossy(&data);
+        assert_eq!(frames, vec![0, 128, 192, 256]);
+        assert_eq!((gaps.len(), gaps[0].byte_offset, gaps[0].byte_len), (1, 183, 199));
+        assert!(matches!(gaps[0].error.code(), ErrorCode::FrameCrcMismatch));
+        // junk between the frames, including a sync code of a header failing the CRC-8
+        let mut data = STEREO16[..382].to_vec();
+        data.extend_from_slice(&[0x12, 0xff, 0xf8, 0x69, 0x18, 0x00, 0x00, 0x00, 0xff]);
+        data.extend_from_slice(&STEREO16[382..]);
+        let (frames, gaps) = decode_lossy(&data);
+        assert_eq!(frames, vec![0, 64, 128, 192, 256]);
+        assert_eq!((gaps.len(), gaps[0].byte_offset, gaps[0].byte_len), (2, 382, 1));
+        assert!(matches!(gaps[0].error.code(), ErrorCode::FrameOutOfSync));
+        assert_eq!((gaps[1].byte_offset, gaps[1].byte_len), (383, 8));
+        assert!(matches!(gaps[1].error.code(), ErrorCode::FrameHeaderCrcMismatch));
+        // the last frame cut short
+        let (frames, gaps) = decode_lossy(&STEREO16[..800]);
+        assert_eq!(frames, vec![0, 64, 128, 192]);
+        assert_eq!((gaps.len(), gaps[0].byte_offset, gaps[0].byte_len), (1, 764, 36));
+        assert!(matches!(gaps[0].error.code(), ErrorCode::Io(_)));
+    }
+
+    #[test]
+    fn test_resync_on_error() {
+        let mut data = STEREO16.to_vec();
+        data[183 + 50] ^= 0x01;
+        let decode = |options: DecodeOptions| -> Result<Vec<u64>> {
+            let mut bytes: &[u8] = &data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::with_options(&mut reader, options)?;
+            let mut frames: Vec<u64> = Vec::new();
+            stream.decode_frames(&mut reader, |frame: &Frame| {
+                frames.push(frame.header.sample_number);
+            })?;
+            Ok(frames)
+        };
+        assert!(matches!(decode(DecodeOptions::default()).unwrap_err().code(), ErrorCode::FrameCrcMismatch));
+        let options = DecodeOptions::default().resync_on_error(true);
+        assert_eq!(decode(options).unwrap(), vec![0, 128, 192, 256]);
+        // the frame lost is told by the signature
+        let options = options.verify_md5(true);
+        assert!(matches!(decode(options).unwrap_err().code(), ErrorCode::Md5Mismatch));
+        // or decoded as is without the CRC
+        let options = options.verify_crc(false).resync_on_error(false);
+        assert!(decode(options).is_err());
+        let options = options.verify_md5(false);
+        assert_eq!(decode(options).unwrap(), vec![0, 64, 128, 192, 256]);
+    }
+
+    #[test]
+    fn test_corruption_without_panic() {
+        // every byte of every file altered in turn decodes to either samples or an error
+        let files: [&[u8]; 3] = [STEREO16, include_bytes!("../testdata/stereo24.flac"), include_bytes!("../testdata/stereo32.flac")];
+        for file in files.iter() {
+            for i in 0..file.len() {
+                let mut data = file.to_vec();
+                data[i] ^= 0xa5;
+                for &verify_crc in &[true, false] {
+                    let options = DecodeOptions { verify_crc, verify_md5: true, ..Default::default() };
+                    let mut bytes: &[u8] = &data;
+                    let mut proxy = DecodingReadProxy::new(&mut bytes);
+                    let mut reader = BitReader::new(&mut proxy);
+                    if let Ok(stream) = Stream::with_options(&mut reader, options) {
+                        let _ = stream.decode_frames(&mut reader, |frame: &Frame<i64>| {
+                            frame.interleaved();
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_stray_metadata() {
+        // a padding and another STREAMINFO after the last block
+        let mut data = STEREO16[..42].to_vec();
+        data.extend_from_slice(&[0x81, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00]);
+        data.extend_from_slice(&[0x80, 0x00, 0x00, 0x22]);
+        data.extend_from_slice(&STEREO16[8..42]);
+        data.extend_from_slice(&STEREO16[42..]);
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let (left, _) = stream.decode_stereo(&mut reader).unwrap();
+        assert_eq!(left.len(), 296);
+        // fails to sync
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::with_options(&mut reader, DecodeOptions::strict()).unwrap();
+        match stream.verify(&mut reader).unwrap_err().code() {
+            ErrorCode::FrameOutOfSync => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_reserved_metadata() {
+        let with_block = |block: &[u8]| {
+            let mut data = STEREO16[..42].to_vec();
+            // clears the last flag of STREAMINFO
+            data[4] &= 0x7f;
+            data.extend_from_slice(block);
+            data.extend_from_slice(&STEREO16[42..]);
+            data
+        };
+        // a reserved type 7 of 2 bytes
+        let data = with_block(&[0x87, 0x00, 0x00, 0x02, 0x00, 0x00]);
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert_eq!(stream.first_frame_offset(), 48);
+        stream.verify(&mut reader).unwrap();
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let error = Stream::with_options(&mut reader, DecodeOptions::strict()).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::InvalidMetadataType));
+        // the type 127 is invalid regardless
+        let data = with_block(&[0xff, 0x00, 0x00, 0x00]);
+        for &options in &[DecodeOptions::default(), DecodeOptions::strict()] {
+            let mut bytes: &[u8] = &data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let error = Stream::with_options(&mut reader, options).err().unwrap();
+            assert!(matches!(error.code(), ErrorCode::InvalidMetadataType));
+        }
+    }
+
+    #[test]
+    fn test_id3_tag() {
+        // an ID3v2.4 tag of 200 bytes in syncsafe, 0x01 0x48
+        let tagged = |flags: u8, trailing: usize, magic: &[u8]| {
+            let mut data = b"ID3\x04\x00".to_vec();
+            data.extend_from_slice(&[flags, 0x00, 0x00, 0x01, 0x48]);
+            data.resize(data.len() + 200 + trailing, 0x55);
+            data.extend_from_slice(magic);
+            data.extend_from_slice(&STEREO16[4..]);
+            data
+        };
+        // with and without the footer
+        for &(flags, trailing) in &[(0x00, 0), (0x10, 10)] {
+            let data = tagged(flags, trailing, b"fLaC");
+            let mut bytes: &[u8] = &data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            assert_eq!(stream.first_frame_offset(), 42 + 210 + trailing as u64);
+            stream.verify(&mut reader).unwrap();
+            let mut bytes: &[u8] = &data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            assert_eq!(StreamInfo::read_from(&mut reader).unwrap().total_samples, 296);
+        }
+        let data = tagged(0x00, 0, b"OggS");
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let error = Stream::new(&mut reader).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::WrongMagic));
+        // not syncsafe
+        let mut data = tagged(0x00, 0, b"fLaC");
+        data[9] = 0xc8;
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let error = Stream::new(&mut reader).err().unwrap();
+        assert!(matches!(error.code(), ErrorCode::WrongMagic));
+    }
+
+    #[test]
+    fn test_frame_stats() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let first_frame_offset = reader.byte_offset();
+        let mut stats: Vec<FrameStats> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| stats.push(frame.stats.clone())).unwrap();
+        let offsets: Vec<u64> = stats.iter()
+            .map(|x| x.byte_offset - first_frame_offset)
+            .collect();
+        assert_eq!(offsets, vec![0, 141, 340, 555, 722]);
+        assert_eq!(stats[0].byte_len, 141);
+        assert_eq!(stats[0].num_samples, 128);
+        assert_eq!(stats[0].bits_per_sample(), 141.0 * 8.0 / 128.0);
+        // compressed below the 16 bits of the source
+        for x in &stats {
+            assert!(x.bits_per_sample() > 0.0 && x.bits_per_sample() < 16.0);
+        }
+        let end = stats.last().map(|x| x.byte_offset + x.byte_len).unwrap();
+        assert_eq!(end, STEREO16.len() as u64);
+    }
+
+    #[test]
+    fn test_frame_size_summary() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let summary = stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap();
+        // frames at 0, 141, 340, 555 and 722 out of 867 bytes
+        assert_eq!(summary.num_frames, 5);
+        assert_eq!(summary.min_frame_size, 141);
+        assert_eq!(summary.max_frame_size, 215);
+        assert_eq!(summary.average_frame_size(), Some(867.0 / 5.0));
+        assert_eq!(stream.stream_info.min_frame_size, 141);
+        assert_eq!(stream.stream_info.max_frame_size, 215);
+        assert!(summary.conforms_to(&stream.stream_info));
+        let mut info = stream.stream_info;
+        info.max_frame_size = 200;
+        assert!(!summary.conforms_to(&info));
+        // unknown sizes filled in
+        info.min_frame_size = 0;
+        info.max_frame_size = 0;
+        summary.update_stream_info(&mut info);
+        assert_eq!(info, stream.stream_info);
+        FrameSizeSummary::default().update_stream_info(&mut info);
+        assert_eq!(info, stream.stream_info);
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let files: &[&[u8]] = &[STEREO16, include_bytes!("../testdata/stereo24.flac")];
+        let mut num_predicted = 0;
+        for &file in files {
+            let mut bytes: &[u8] = file;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            stream.decode_frames(&mut reader, |frame: &Frame| {
+                for subframe in &frame.stats.subframes {
+                    match subframe.method {
+                        PredictionMethod::Fixed(order) | PredictionMethod::Fir(order) => {
+                            assert_eq!(subframe.warm_up, order);
+                            num_predicted += 1;
+                        },
+                        _ => assert_eq!(subframe.warm_up, 0)
+                    }
+                }
+            }).unwrap();
+        }
+        assert!(num_predicted > 0);
+    }
+
+    #[test]
+    fn test_inspect_residuals() {
+        for &inspect in &[false, true] {
+            let mut bytes: &[u8] = STEREO16;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let options = DecodeOptions::default().inspect_residuals(inspect);
+            let stream = Stream::with_options(&mut reader, options).unwrap();
+            let mut num_inspected = 0;
+            stream.decode_frames(&mut reader, |frame: &Frame| {
+                for subframe in &frame.stats.subframes {
+                    match (subframe.method, &subframe.residual) {
+                        (PredictionMethod::Fixed(_), Some(residual)) | (PredictionMethod::Fir(_), Some(residual)) => {
+                            assert!(inspect);
+                            assert!(residual.parameter_bits == 4 || residual.parameter_bits == 5);
+                            assert_eq!(residual.partitions.len(), 1 << residual.partition_order);
+                            num_inspected += 1;
+                        },
+                        (PredictionMethod::Fixed(_), None) | (PredictionMethod::Fir(_), None) => assert!(!inspect),
+                        (_, residual) => assert!(residual.is_none())
+                    }
+                }
+            }).unwrap();
+            assert_eq!(num_inspected > 0, inspect);
+        }
+    }
+
+    #[test]
+    fn test_frame_footer() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut frames: Vec<(FrameStats, FrameFooter)> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| frames.push((frame.stats.clone(), frame.footer))).unwrap();
+        for (stats, footer) in frames {
+            // the footer is at the end of the frame and covers the bytes before it
+            let end = (stats.byte_offset + stats.byte_len) as usize;
+            let crc16 = u16::from_be_bytes([STEREO16[end - 2], STEREO16[end - 1]]);
+            assert_eq!(footer.crc16, crc16);
+            let mut hasher = HasherCrc16Buypass::new();
+            hasher.hash(&STEREO16[stats.byte_offset as usize..end - 2]);
+            assert_eq!(hasher.state(), crc16);
+        }
+    }
+
+    #[derive(Debug)]
+    enum SinkError {
+        Full,
+        Decode(Error)
+    }
+
+    impl From<Error> for SinkError {
+        fn from(error: Error) -> Self {
+            SinkError::Decode(error)
+        }
+    }
+
+    #[test]
+    fn test_try_decode_frames() {
+        let decode = |data: &[u8], capacity: usize| -> core::result::Result<Vec<u64>, SinkError> {
+            let mut bytes: &[u8] = data;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader)?;
+            let mut frames: Vec<u64> = Vec::new();
+            stream.try_decode_frames(&mut reader, |frame: &Frame| {
+                if frames.len() == capacity {
+                    return Err(SinkError::Full)
+                }
+                frames.push(frame.header.sample_number);
+                Ok(())
+            })?;
+            Ok(frames)
+        };
+        assert_eq!(decode(STEREO16, 5).unwrap(), vec![0, 64, 128, 192, 256]);
+        // aborted by the sink
+        assert!(matches!(decode(STEREO16, 2).unwrap_err(), SinkError::Full));
+        // errors of decoding come through the error of the sink
+        let mut data = STEREO16.to_vec();
+        data[183 + 50] ^= 0x01;
+        match decode(&data, 5).unwrap_err() {
+            SinkError::Decode(e) => assert!(matches!(e.code(), ErrorCode::FrameCrcMismatch)),
+            e => panic!("unexpected error {:?}", e)
+        }
+    }
+
+    #[test]
+    fn test_decode_frames_cancellable() {
+        use alloc::sync::Arc;
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut num_frames = 0;
+        stream.decode_frames_cancellable(&mut reader, &cancel, |_: &Frame| {
+            num_frames += 1;
+            if num_frames == 2 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        }).unwrap();
+        assert_eq!(num_frames, 2);
+    }
+
+    #[test]
+    fn test_md5_incremental() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut hasher = HasherMd5::new();
+        let mut digests: Vec<u128> = Vec::new();
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            frame.update_md5(&mut hasher);
+            digests.push(hasher.state());
+        }).unwrap();
+        assert_eq!(digests.len(), 5);
+        // the first 128 samples
+        assert_eq!(digests[1], 0xd2f1112ede4cc6afa33d5785e31ac9c2);
+        assert_eq!(digests[4], stream.stream_info.signature);
+        // 24 and 32 bits per sample take 3 and 4 bytes
+        let files: &[&[u8]] = &[include_bytes!("../testdata/stereo24.flac"), include_bytes!("../testdata/stereo32.flac")];
+        for &file in files {
+            let mut bytes: &[u8] = file;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            let mut hasher = HasherMd5::new();
+            stream.decode_frames(&mut reader, |frame: &Frame| frame.update_md5(&mut hasher)).unwrap();
+            assert_eq!(hasher.state(), stream.stream_info.signature);
+        }
+    }
+
+    #[test]
+    fn test_verify_crc() {
+        let expected = decode_all(STEREO16);
+        let mut data = STEREO16.to_vec();
+        // the CRC-8 of the first frame header and the CRC-16 of every frame
+        data[48] ^= 0xff;
+        for &end in &[183, 382, 597, 764, 909] {
+            data[end - 1] ^= 0xff;
+        }
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        match stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap_err().code() {
+            ErrorCode::FrameHeaderCrcMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        let options = DecodeOptions { verify_crc: false, ..Default::default() };
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::with_options(&mut reader, options).unwrap();
+        let mut channels: Vec<Vec<i32>> = vec![Vec::new(); 2];
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            for (channel, block) in channels.iter_mut().zip(frame.blocks.iter()) {
+                channel.extend_from_slice(block);
+            }
+        }).unwrap();
+        assert_eq!(channels, expected);
+    }
+
+    #[test]
+    fn test_md5_mismatch() {
+        // alter the signature at the end of STREAMINFO
+        let mut data = STEREO16.to_vec();
+        data[40] ^= 0x01;
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        match stream.verify(&mut reader).unwrap_err().code() {
+            ErrorCode::Md5Mismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        // opt-in when decoding
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert!(stream.decode_frames(&mut reader, |_: &Frame| ()).is_ok());
+        let options = DecodeOptions { verify_md5: true, ..Default::default() };
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::with_options(&mut reader, options).unwrap();
+        match stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap_err().code() {
+            ErrorCode::Md5Mismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        // unknown
+        for byte in &mut data[26..42] {
+            *byte = 0;
+        }
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert!(stream.verify(&mut reader).is_ok());
+    }
+
+    #[test]
+    fn test_decode_channels() {
+        let expected = decode_all(STEREO16);
+        // an altered signature goes unverified, as the skipped channel would not match it
+        let mut data = STEREO16.to_vec();
+        data[40] ^= 0x01;
+        let options = DecodeOptions::default().verify_md5(true).with_channels(&[0]);
+        let mut bytes: &[u8] = &data;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::with_options(&mut reader, options).unwrap();
+        let mut channels: Vec<Vec<i32>> = vec![vec![]; 2];
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            for (channel, block) in channels.iter_mut().zip(frame.blocks.iter()) {
+                channel.extend_from_slice(block);
+            }
+        }).unwrap();
+        assert_eq!(channels[0], expected[0]);
+        // the independent frames at both ends leave the right channel silent, while
+        // the stereo frames in between are decoded regardless
+        assert!(channels[1][..64].iter().all(|&x| x == 0));
+        assert!(channels[1][256..].iter().all(|&x| x == 0));
+        assert_eq!(channels[1][64..256], expected[1][64..256]);
+        assert_ne!(channels[1], expected[1]);
+    }
+
+    #[test]
+    fn test_block_size_mismatch() {
+        let options = DecodeOptions::strict();
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::with_options(&mut reader, options).unwrap();
+        stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap();
+        // the last frame of 40 samples moved to the beginning
+        let reordered = [&STEREO16[..42], &STEREO16[764..], &STEREO16[42..764]].concat();
+        let mut bytes: &[u8] = &reordered;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::with_options(&mut reader, options).unwrap();
+        match stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap_err().code() {
+            ErrorCode::FrameBlockSizeMismatch => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+        // tolerated unless decoding strictly
+        let mut bytes: &[u8] = &reordered;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let summary = stream.decode_frames(&mut reader, |_: &Frame| ()).unwrap();
+        assert_eq!(summary.num_frames, 5);
+    }
+
+    #[test]
+    fn test_decode_next_frame() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut blocks: Vec<FrameBuffer> = stream.allocate_blocks();
+        assert_eq!(blocks.len(), 2);
+        let header = stream.decode_next_frame(&mut reader, &mut blocks).unwrap().unwrap();
+        assert_eq!((header.coded_number, header.block_size), (0, 64));
+        assert_eq!(blocks[0].len(), 64);
+        assert_eq!(reader.byte_offset(), 42 + 141);
+        let mut block_sizes: Vec<usize> = Vec::new();
+        while let Some(header) = stream.decode_next_frame(&mut reader, &mut blocks).unwrap() {
+            block_sizes.push(header.block_size);
+        }
+        assert_eq!(block_sizes, vec![64, 64, 64, 40]);
+        assert_eq!(reader.byte_offset(), 42 + 867);
+        // samples of the last frame
+        assert_eq!(blocks[1].len(), 40);
+    }
+
+    #[test]
+    fn test_decode_next_frame_into() {
+        let expected = decode_all(STEREO16);
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut blocks: Vec<FrameBuffer> = stream.allocate_blocks();
+        let mut scratch: FrameScratch = stream.allocate_scratch();
+        let mut left = [0i32; 64];
+        let mut right = [0i32; 64];
+        let mut channels: Vec<Vec<i32>> = vec![vec![]; 2];
+        while let Some(header) = stream.decode_next_frame_into(&mut reader, &mut blocks, &mut scratch, &mut [&mut left, &mut right]).unwrap() {
+            channels[0].extend_from_slice(&left[..header.block_size]);
+            channels[1].extend_from_slice(&right[..header.block_size]);
+        }
+        assert_eq!(channels, expected);
+        // too short for the block, or too few
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut short = [0i32; 63];
+        let error = stream.decode_next_frame_into(&mut reader, &mut blocks, &mut scratch, &mut [&mut left, &mut short]).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::FrameBufferUnallocated));
+        let error = stream.decode_next_frame_into(&mut reader, &mut blocks, &mut scratch, &mut [&mut left]).unwrap_err();
+        assert!(matches!(error.code(), ErrorCode::FrameBufferUnallocated));
+    }
+
+    #[test]
+    fn test_decode_stereo() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let (left, right) = stream.decode_stereo(&mut reader).unwrap();
+        assert_eq!(left.len(), 296);
+        assert_eq!(right.len(), 296);
+        assert_eq!((left[0], right[0]), (37, 0));
+        assert_eq!((left[129], right[129]), (1775, 5549));
+    }
+
+    #[test]
+    fn test_decode_stereo_forged_total_samples() {
+        let data = forged_total_samples();
+        let mut reader = SliceBitReader::new(&data);
+        let stream = Stream::new(&mut reader).unwrap();
+        let (left, right) = stream.decode_stereo(&mut reader).unwrap();
+        assert_eq!((left.len(), right.len()), (296, 296));
+        assert_eq!((left[129], right[129]), (1775, 5549));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_decode_downmix_stereo() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let (left, right) = stream.decode_downmix_stereo(&mut reader, &DownmixCoefficients::default()).unwrap();
+        // stereo passes through as is
+        assert_eq!(left.len(), 296);
+        assert_eq!((left[0], right[0]), (37, 0));
+        assert_eq!((left[129], right[129]), (1775, 5549));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_decode_downmix_stereo_forged_total_samples() {
+        let data = forged_total_samples();
+        let mut reader = SliceBitReader::new(&data);
+        let stream = Stream::new(&mut reader).unwrap();
+        let (left, right) = stream.decode_downmix_stereo(&mut reader, &DownmixCoefficients::default()).unwrap();
+        assert_eq!((left.len(), right.len()), (296, 296));
+        assert_eq!((left[0], right[0]), (37, 0));
+    }
+
+    #[test]
+    fn test_decode_windows() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let (left, right) = stream.decode_stereo(&mut reader).unwrap();
+        // larger than the block size of 64
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut windows: Vec<Vec<Vec<i32>>> = Vec::new();
+        stream.decode_windows(&mut reader, 100, 50, |x| {
+            windows.push(x.iter().map(|x| x.to_vec()).collect());
+        }).unwrap();
+        // starting at 0, 50, 100, 150 out of 296 samples
+        assert_eq!(windows.len(), 4);
+        for (i, window) in windows.iter().enumerate() {
+            let expected_left: Vec<i32> = left[i * 50..i * 50 + 100].iter().map(|&x| x as i32).collect();
+            let expected_right: Vec<i32> = right[i * 50..i * 50 + 100].iter().map(|&x| x as i32).collect();
+            assert_eq!(window[0], expected_left);
+            assert_eq!(window[1], expected_right);
+        }
+        // hopping over samples
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut firsts: Vec<i32> = Vec::new();
+        stream.decode_windows(&mut reader, 10, 100, |x| firsts.push(x[0][0])).unwrap();
+        assert_eq!(firsts, vec![left[0] as i32, left[100] as i32, left[200] as i32]);
+        // an empty window or hop never advances
+        for &(window_size, hop) in &[(0, 1), (1, 0)] {
+            let mut bytes: &[u8] = STEREO16;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            let error = stream.decode_windows(&mut reader, window_size, hop, |_| ()).err().unwrap();
+            assert!(matches!(error.code(), ErrorCode::InvalidArgument));
+        }
+    }
+
+    #[test]
+    fn test_decode_stereo_unsupported() {
+        let mut bytes: &[u8] = include_bytes!("../testdata/stereo24.flac");
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        match stream.decode_stereo(&mut reader).unwrap_err().code() {
+            ErrorCode::UnsupportedStreamFormat => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    #[test]
+    fn test_populate_seek_table() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let mut table = SeekTable {
+            points: vec![SeekPoint::placeholder(); 3]
+        };
+        stream.populate_seek_table(&mut reader, &mut table).unwrap();
+        assert_eq!(table.points, vec![
+            SeekPoint { sample_number: 0, stream_offset: 0, frame_samples: 64 },
+            SeekPoint { sample_number: 64, stream_offset: 141, frame_samples: 64 },
+            SeekPoint { sample_number: 192, stream_offset: 555, frame_samples: 64 }
+        ]);
+    }
+
+    #[test]
+    fn test_populate_seek_table_keeps_existing_points() {
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let existing = SeekPoint { sample_number: 0, stream_offset: 0, frame_samples: 64 };
+        let mut table = SeekTable {
+            points: vec![existing, SeekPoint::placeholder(), SeekPoint::placeholder()]
+        };
+        stream.populate_seek_table(&mut reader, &mut table).unwrap();
+        // the first placeholder targets the sample 0 which is already present
+        assert_eq!(table.points[0], existing);
+        assert_eq!(table.points[1], SeekPoint { sample_number: 128, stream_offset: 340, frame_samples: 64 });
+        assert!(table.points[2].is_placeholder());
+    }
+
+    fn decode_all(bytes: &[u8]) -> Vec<Vec<i32>> {
+        decode_bytes(bytes).unwrap().1
+    }
+
+    #[test]
+    fn test_decode_bytes() {
+        let (info, channels) = decode_bytes(STEREO16).unwrap();
+        assert_eq!((info.sample_rate, info.number_of_channels, info.total_samples), (44100, 2, 296));
+        assert_eq!(channels.len(), 2);
+        assert!(channels.iter().all(|x| x.len() == 296));
+        let (left, right) = {
+            let mut bytes: &[u8] = STEREO16;
+            let mut proxy = DecodingReadProxy::new(&mut bytes);
+            let mut reader = BitReader::new(&mut proxy);
+            let stream = Stream::new(&mut reader).unwrap();
+            stream.decode_stereo(&mut reader).unwrap()
+        };
+        assert_eq!(channels[0], left.iter().map(|&x| x as i32).collect::<Vec<i32>>());
+        assert_eq!(channels[1], right.iter().map(|&x| x as i32).collect::<Vec<i32>>());
+        match decode_bytes(&STEREO16[..40]).unwrap_err().code() {
+            ErrorCode::Io(_) => (),
+            code => panic!("unexpected error {:?}", code)
+        }
+    }
+
+    // STEREO16 claiming 2^36-1 samples, the most the 36 bits of STREAMINFO hold
+    fn forged_total_samples() -> Vec<u8> {
+        let mut data = STEREO16.to_vec();
+        data[21] |= 0x0f;
+        data[22..26].copy_from_slice(&[0xff; 4]);
+        data
+    }
+
+    #[test]
+    fn test_decode_bytes_forged_total_samples() {
+        let data = forged_total_samples();
+        let info = Stream::new(&mut SliceBitReader::new(&data)).unwrap().stream_info;
+        assert_eq!(info.total_samples, (1 << 36) - 1);
+        assert_eq!(info.reserved_samples(), 1 << 20);
+        // decodes the frames there are without reserving for the total
+        let (_, channels) = decode_bytes(&data).unwrap();
+        assert!(channels.iter().all(|x| x.len() == 296));
+    }
+
+    // seeks to the target in a fresh stream, then decodes the rest of it
+    #[cfg(feature = "std")]
+    fn seek_and_decode(bytes: &[u8], target_sample: u64) -> (u64, Vec<Vec<i32>>) {
+        let mut cursor = Cursor::new(bytes);
+        let stream = {
+            let mut proxy = DecodingReadProxy::new(&mut cursor);
+            let mut reader = BitReader::new(&mut proxy);
+            Stream::with_options(&mut reader, DecodeOptions { verify_md5: true, ..Default::default() }).unwrap()
+        };
+        let position = stream.seek(&mut cursor, target_sample).unwrap();
+        let mut proxy = DecodingReadProxy::new(&mut cursor);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut channels: Vec<Vec<i32>> = vec![Vec::new(); 2];
+        stream.decode_frames(&mut reader, |frame: &Frame| {
+            // numbered from the beginning of the stream even after seeking
+            assert_eq!(frame.header.sample_number, position + channels[0].len() as u64);
+            for (channel, block) in channels.iter_mut().zip(frame.blocks.iter()) {
+                channel.extend_from_slice(block);
+            }
+        }).unwrap();
+        (position, channels)
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_seek_with_seek_table() {
+        let expected = decode_all(METADATA);
+        // points at 0 and 128
+        for &(target, position) in &[(0, 0), (100, 0), (128, 128), (300, 128)] {
+            let (actual, channels) = seek_and_decode(METADATA, target);
+            assert_eq!(actual, position);
+            assert_eq!(channels[0], &expected[0][position as usize..]);
+            assert_eq!(channels[1], &expected[1][position as usize..]);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_seek_without_seek_table() {
+        let expected = decode_all(STEREO16);
+        for &(target, position) in &[(0, 0), (63, 0), (64, 64), (200, 192), (295, 256), (1000, 256)] {
+            let (actual, channels) = seek_and_decode(STEREO16, target);
+            assert_eq!(actual, position);
+            assert_eq!(channels[0], &expected[0][position as usize..]);
+            assert_eq!(channels[1], &expected[1][position as usize..]);
+        }
+    }
+
+    #[test]
+    fn test_vorbis_comment() {
+        let mut bytes: &[u8] = METADATA;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let comment = stream.vorbis_comment.unwrap();
+        assert_eq!(comment.vendor, "reference libFLAC 1.3.2 20170101");
+        assert_eq!(comment.comments[1], ("ARTIST".to_string(), "Suono".to_string()));
+        // no tags
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert_eq!(stream.vorbis_comment, None);
+    }
+
+    #[test]
+    fn test_seek_table() {
+        let mut bytes: &[u8] = METADATA;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let table = stream.seek_table.unwrap();
+        assert_eq!(table.points.len(), 3);
+        assert_eq!(table.points[0], SeekPoint { sample_number: 0, stream_offset: 0, frame_samples: 64 });
+        assert!(!table.points[1].is_placeholder());
+        assert!(table.points[2].is_placeholder());
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert_eq!(stream.seek_table, None);
+    }
+
+    #[test]
+    fn test_pictures() {
+        let mut bytes: &[u8] = METADATA;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert_eq!(stream.pictures.len(), 1);
+        let picture = &stream.pictures[0];
+        assert_eq!(picture.mime_type, "image/png");
+        assert_eq!((picture.width, picture.height), (16, 8));
+        assert_eq!(picture.data.len(), 40);
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert!(stream.pictures.is_empty());
+    }
+
+    #[test]
+    fn test_applications() {
+        let mut bytes: &[u8] = METADATA;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert_eq!(stream.applications, vec![Application { id: *b"ATCH", data: b"project=demo".to_vec() }]);
+        let mut bytes: &[u8] = STEREO16;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        assert!(stream.applications.is_empty());
+    }
+
+    #[test]
+    fn test_for_each_metadata() {
+        let mut bytes: &[u8] = METADATA;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let mut blocks: Vec<(MetadataType, Vec<u8>)> = Vec::new();
+        Stream::for_each_metadata(&mut reader, |header, body| {
+            assert_eq!(header.length_in_bytes, body.len());
+            blocks.push((header.metadata_type, body.to_vec()));
+        }).unwrap();
+        let types: Vec<MetadataType> = blocks.iter().map(|x| x.0).collect();
+        assert_eq!(types, vec![
+            MetadataType::StreamInfo,
+            MetadataType::Seektable,
+            MetadataType::VorbisComment,
+            MetadataType::Picture,
+            MetadataType::Application,
+            MetadataType::Cuesheet,
+            MetadataType::Reserved,
+            MetadataType::Padding
+        ]);
+        assert_eq!(blocks[0].1.len(), 34);
+        assert_eq!(blocks[6].1, vec![1, 2, 3]);
+        assert_eq!(blocks[7].1, vec![0; 10]);
+        // followed by the same frames as the stream without metadata
+        let frames_len = (STEREO16.len() - 42) as u64;
+        assert_eq!(reader.byte_offset(), METADATA.len() as u64 - frames_len);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut bytes: &[u8] = METADATA;
+        let mut proxy = DecodingReadProxy::new(&mut bytes);
+        let mut reader = BitReader::new(&mut proxy);
+        let stream = Stream::new(&mut reader).unwrap();
+        let blocks: Vec<Metadata> = stream.metadata()
+            .map(|x| x.unwrap())
+            .collect();
+        assert_eq!(blocks.len(), 8);
+        assert_eq!(blocks[0], Metadata::StreamInfo(stream.stream_info));
+        match &blocks[1] {
+            Metadata::SeekTable(table) => {
+                assert_eq!(table.points.len(), 3);
+                assert_eq!(table.points[1], SeekPoint { sample_number: 128, stream_offset: 340, frame_samples: 64 });
+                assert!(table.points[2].is_placeholder());
+            },
+            x => panic!("unexpected {:?}", x)
+        }
+        match &blocks[2] {
+            Metadata::VorbisComment(comment) => {
+                assert_eq!(comment.vendor, "reference libFLAC 1.3.2 20170101");
+                assert_eq!(comment.comments, vec![
+                    ("TITLE".to_string(), "Sine".to_string()),
+                    ("ARTIST".to_string(), "Suono".to_string()),
+                    ("album".to_string(), "Test Tones".to_string())
+                ]);
+            },
+            x => panic!("unexpected {:?}", x)
+        }
+        match &blocks[3] {
+            Metadata::Picture(picture) => {
+                assert_eq!(picture.picture_type, 3);
+                assert_eq!(picture.mime_type, "image/png");
+                assert_eq!(picture.description, "Front cover");
+                assert_eq!((picture.width, picture.height, picture.color_depth, picture.colors), (16, 8, 24, 0));
+                assert_eq!(picture.data, (0..40).collect::<Vec<u8>>());
+            },
+            x => panic!("unexpected {:?}", x)
+        }
+        match &blocks[4] {
+            Metadata::Application(application) => {
+                assert_eq!(&application.id, b"ATCH");
+                assert_eq!(application.data, b"project=demo".to_vec());
+            },
+            x => panic!("unexpected {:?}", x)
+        }
+        match &blocks[5] {
+            Metadata::CueSheet(cue_sheet) => {
+                assert_eq!(cue_sheet.media_catalog_number, "1234567890123");
+                assert_eq!(cue_sheet.lead_in_samples, 88200);
+                assert!(cue_sheet.is_cd);
+                assert_eq!(cue_sheet.tracks.len(), 2);
+                let track = &cue_sheet.tracks[0];
+                assert_eq!((track.offset, track.number), (0, 1));
+                assert_eq!(track.isrc, "USABC1234567");
+                assert!(track.is_audio);
+                assert!(!track.pre_emphasis);
+                assert_eq!(track.indices.len(), 1);
+                // lead-out
+                assert_eq!((cue_sheet.tracks[1].offset, cue_sheet.tracks[1].number), (296, 170));
+            },
+            x => panic!("unexpected {:?}", x)
+        }
+        assert_eq!(blocks[6], Metadata::Unknown(vec![1, 2, 3]));
+        assert_eq!(blocks[7], Metadata::Padding(10));
+        // frames follow the metadata
+        assert!(stream.verify(&mut reader).is_ok());
     }
 }
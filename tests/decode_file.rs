@@ -0,0 +1,84 @@
+use suono::{decode_file_i16, FlacReader};
+use suono::frame::Frame;
+
+fn testdata(name: &str) -> String {
+    format!("{}/testdata/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn test_decode_file_i16() {
+    let (samples, sample_rate, channels) = decode_file_i16(testdata("stereo16.flac")).unwrap();
+    assert_eq!(sample_rate, 44100);
+    assert_eq!(channels, 2);
+    assert_eq!(samples.len(), 296 * 2);
+    assert_eq!(&samples[..2], &[37, 0]);
+    assert_eq!(&samples[258..260], &[1775, 5549]);
+}
+
+#[test]
+fn test_decode_file_i16_truncated() {
+    let (samples, sample_rate, channels) = decode_file_i16(testdata("stereo24.flac")).unwrap();
+    assert_eq!(sample_rate, 96000);
+    assert_eq!(channels, 2);
+    assert_eq!(samples.len(), 240 * 2);
+    // 886846 and 2601774 in 24 bits
+    assert_eq!(&samples[..2], &[3464, 10163]);
+    // full scale
+    assert_eq!(&samples[394..398], &[i16::MAX, i16::MIN, i16::MIN, i16::MAX]);
+}
+
+#[test]
+fn test_decode_file_i16_forged_total_samples() {
+    // STEREO16 claiming 2^36-1 samples, which are not reserved up front
+    let mut data = std::fs::read(testdata("stereo16.flac")).unwrap();
+    data[21] |= 0x0f;
+    data[22..26].copy_from_slice(&[0xff; 4]);
+    let path = std::env::temp_dir().join(format!("suono-forged-{}.flac", std::process::id()));
+    std::fs::write(&path, &data).unwrap();
+    let result = decode_file_i16(&path);
+    std::fs::remove_file(&path).unwrap();
+    let (samples, _, channels) = result.unwrap();
+    assert_eq!(channels, 2);
+    assert_eq!(samples.len(), 296 * 2);
+    assert_eq!(&samples[258..260], &[1775, 5549]);
+}
+
+#[test]
+fn test_decode_file_i16_not_found() {
+    assert!(decode_file_i16(testdata("missing.flac")).is_err());
+}
+
+#[test]
+fn test_flac_reader() {
+    let mut flac = FlacReader::open(testdata("metadata.flac")).unwrap();
+    assert_eq!(flac.stream_info().total_samples, 296);
+    assert!(flac.stream().vorbis_comment.is_some());
+    let mut offsets: Vec<u64> = Vec::new();
+    let summary = flac.decode_frames(|frame: &Frame| offsets.push(frame.stats.byte_offset)).unwrap();
+    assert_eq!(summary.num_frames, 5);
+    // from the beginning of the file
+    assert_eq!(offsets[0], flac.stream().first_frame_offset());
+    assert_eq!(offsets[1], offsets[0] + 141);
+}
+
+#[test]
+fn test_flac_reader_seek() {
+    let mut flac = FlacReader::open(testdata("stereo16.flac")).unwrap();
+    let (left, right) = flac.with_reader(|stream, reader| stream.decode_stereo(reader)).unwrap();
+    assert_eq!(flac.seek(200).unwrap(), 192);
+    let (left_sought, right_sought) = flac.with_reader(|stream, reader| stream.decode_stereo(reader)).unwrap();
+    assert_eq!(left_sought, &left[192..]);
+    assert_eq!(right_sought, &right[192..]);
+}
+
+#[test]
+fn test_flac_reader_failing_sink() {
+    let mut flac = FlacReader::open(testdata("stereo16.flac")).unwrap();
+    let mut frames = 0;
+    let result = flac.try_decode_frames(|_: &Frame| -> Result<(), Box<dyn std::error::Error>> {
+        frames += 1;
+        Err("disk full".into())
+    });
+    assert_eq!(result.unwrap_err().to_string(), "disk full");
+    assert_eq!(frames, 1);
+}
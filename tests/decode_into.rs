@@ -0,0 +1,68 @@
+// restoring the channels in parallel allocates for the threads
+#![cfg(not(feature = "parallel"))]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use suono::frame::{FrameBuffer, FrameScratch};
+use suono::sample::Sample;
+use suono::slice::SliceBitReader;
+use suono::stream::Stream;
+
+// counts the allocations of the current thread, leaving those of the test harness out
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|x| x.set(x.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|x| x.set(x.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|x| x.get())
+}
+
+// decodes the frames of the stream into buffers allocated up front, counting the allocations
+fn decoding_allocations<S: Sample>(data: &[u8], num_frames: usize) -> usize {
+    let mut reader = SliceBitReader::new(data);
+    let stream = Stream::new(&mut reader).unwrap();
+    let info = stream.stream_info;
+    let mut blocks: Vec<FrameBuffer<S>> = stream.allocate_blocks();
+    let mut scratch: FrameScratch<S> = stream.allocate_scratch();
+    let mut channels: Vec<Vec<S>> = vec![vec![S::default(); info.max_block_size]; info.number_of_channels];
+    let mut slices: Vec<&mut [S]> = channels.iter_mut().map(|x| &mut x[..]).collect();
+    let before = allocations();
+    for _ in 0..num_frames {
+        stream.decode_next_frame_into(&mut reader, &mut blocks, &mut scratch, &mut slices).unwrap().unwrap();
+    }
+    let after = allocations();
+    // the end of stream is told by an I/O error, which may allocate
+    assert!(stream.decode_next_frame_into(&mut reader, &mut blocks, &mut scratch, &mut slices).unwrap().is_none());
+    after - before
+}
+
+#[test]
+fn test_decode_next_frame_into_no_allocation() {
+    let stereo16: &[u8] = include_bytes!("../testdata/stereo16.flac");
+    let stereo24: &[u8] = include_bytes!("../testdata/stereo24.flac");
+    // the side channels of 16 bits go through the wide buffer of the scratch
+    assert_eq!(decoding_allocations::<i16>(stereo16, 5), 0);
+    assert_eq!(decoding_allocations::<i32>(stereo16, 5), 0);
+    assert_eq!(decoding_allocations::<i32>(stereo24, 4), 0);
+}